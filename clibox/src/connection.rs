@@ -1,5 +1,7 @@
 use crate::{NodeId, PortIndex};
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Connection {
     pub output_id: NodeId,
     pub output_port: PortIndex,
@@ -0,0 +1,83 @@
+use crate::{NodeId, PortIndex, PortKind};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    NoRenderedNode,
+    NodeNotFound(NodeId),
+    InputPortNotFound(NodeId, PortIndex),
+    OutputPortNotFound(NodeId, PortIndex),
+    /// An output of the first kind can't be connected to an input of the second kind.
+    IncompatibleKinds(PortKind, PortKind),
+    /// Another node already has this id.
+    DuplicateId(NodeId),
+    /// Another node already has this name.
+    DuplicateName(String),
+    /// The node can't be copied because its type is not in the function repository.
+    UnknownNodeType(NodeId, String),
+    /// The node is part of a cycle, so the network has no render order.
+    Cycle(NodeId),
+    /// The input port has no connection to remove.
+    NotConnected(NodeId, PortIndex),
+    /// The value can't be converted to the kind of the input port.
+    InvalidValue(NodeId, PortIndex, String),
+    /// An edit failed with the first error, and undoing the part that was already applied failed
+    /// with the second. The network is left partly changed.
+    RollbackFailed(Box<NetworkError>, Box<NetworkError>),
+    /// The node's function could not render, e.g. because an input value was invalid.
+    RenderFailed(NodeId, String),
+    /// The node was skipped because the given upstream node failed to render.
+    UpstreamFailed(NodeId, NodeId),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::NoRenderedNode => write!(f, "No rendered node."),
+            NetworkError::NodeNotFound(id) => write!(f, "Node {} could not be found.", id),
+            NetworkError::InputPortNotFound(id, port) => {
+                write!(f, "Input port {} of node {} could not be found.", port, id)
+            }
+            NetworkError::OutputPortNotFound(id, port) => {
+                write!(f, "Output port {} of node {} could not be found.", port, id)
+            }
+            NetworkError::IncompatibleKinds(output_kind, input_kind) => write!(
+                f,
+                "An output of kind {:?} can't be connected to an input of kind {:?}.",
+                output_kind, input_kind
+            ),
+            NetworkError::DuplicateId(id) => write!(f, "A node with id {} already exists.", id),
+            NetworkError::DuplicateName(name) => {
+                write!(f, "A node named \"{}\" already exists.", name)
+            }
+            NetworkError::UnknownNodeType(id, type_name) => {
+                write!(f, "Node {} has unknown type \"{}\".", id, type_name)
+            }
+            NetworkError::Cycle(id) => write!(f, "Node {} is part of a cycle.", id),
+            NetworkError::NotConnected(id, port) => {
+                write!(f, "Input port {} of node {} is not connected.", port, id)
+            }
+            NetworkError::InvalidValue(id, port, value) => write!(
+                f,
+                "\"{}\" is not a valid value for input port {} of node {}.",
+                value, port, id
+            ),
+            NetworkError::RollbackFailed(error, rollback_error) => write!(
+                f,
+                "{} Undoing the edit failed as well: {}",
+                error, rollback_error
+            ),
+            NetworkError::RenderFailed(id, message) => {
+                write!(f, "Node {} failed to render: {}", id, message)
+            }
+            NetworkError::UpstreamFailed(id, failed_id) => write!(
+                f,
+                "Node {} was skipped because node {} failed to render.",
+                id, failed_id
+            ),
+        }
+    }
+}
+
+impl Error for NetworkError {}
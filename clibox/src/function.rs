@@ -1,19 +1,114 @@
-use crate::{Node, RenderContext};
-// use std::collections::HashMap;
+use crate::{Network, NetworkError, Node, RenderContext};
+use std::collections::HashMap;
 
-pub trait Function {
+/// Functions are shared between the threads of a parallel render, so they have to be
+/// `Send + Sync`.
+pub trait Function: Send + Sync {
     fn setup(&self, node: &mut Node);
-    fn render(&self, node: &Node, ctx: &mut RenderContext);
+    /// Compute the node's outputs. An error is recorded on the node and the nodes downstream
+    /// of it are skipped.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError>;
+
+    /// Whether the outputs depend on the frame or time of the render context. Such nodes are
+    /// rendered again for every frame, even if their inputs didn't change.
+    fn is_time_dependent(&self) -> bool {
+        false
+    }
+
+    /// The inner network, if this function renders a subnetwork.
+    fn subnetwork(&self) -> Option<&Network> {
+        None
+    }
+}
+
+pub type FunctionFactory = Box<dyn Fn() -> Box<dyn Function>>;
+
+/// Describes a node type, e.g. to show it in a node palette.
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub type_name: String,
+    pub category: String,
+    pub description: String,
+}
+
+struct FunctionEntry {
+    info: FunctionInfo,
+    factory: FunctionFactory,
+}
+
+/// All node types that can be created, keyed by type name.
+#[derive(Default)]
+pub struct FunctionRepository {
+    functions: HashMap<String, FunctionEntry>,
 }
 
-// pub struct FunctionRepository {
-//     functions: HashMap<String, Box<Function>>,
-// }
-
-// impl FunctionRepository {
-//     pub fn new() -> FunctionRepository {
-//         FunctionRepository {
-//             functions: HashMap::new(),
-//         }
-//     }
-// }
+impl FunctionRepository {
+    /// Create an empty repository.
+    pub fn new() -> FunctionRepository {
+        FunctionRepository::default()
+    }
+
+    /// Create a repository containing all built-in node types.
+    pub fn with_builtins() -> FunctionRepository {
+        let mut repository = FunctionRepository::new();
+        crate::functions::register_builtins(&mut repository);
+        repository
+    }
+
+    /// Register a node type. The factory is called for every node created with this type.
+    /// Registering an existing type name replaces it.
+    pub fn register<F>(&mut self, type_name: &str, category: &str, description: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Function> + 'static,
+    {
+        let info = FunctionInfo {
+            type_name: type_name.to_owned(),
+            category: category.to_owned(),
+            description: description.to_owned(),
+        };
+        let entry = FunctionEntry {
+            info,
+            factory: Box::new(factory),
+        };
+        self.functions.insert(type_name.to_owned(), entry);
+    }
+
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.functions.contains_key(type_name)
+    }
+
+    pub fn get_info(&self, type_name: &str) -> Option<&FunctionInfo> {
+        self.functions.get(type_name).map(|e| &e.info)
+    }
+
+    pub fn new_function(&self, type_name: &str) -> Option<Box<dyn Function>> {
+        self.functions.get(type_name).map(|e| (e.factory)())
+    }
+
+    /// All registered node types, sorted by category and type name.
+    pub fn function_infos(&self) -> Vec<&FunctionInfo> {
+        let mut infos: Vec<&FunctionInfo> = self.functions.values().map(|e| &e.info).collect();
+        infos.sort_by(|a, b| (&a.category, &a.type_name).cmp(&(&b.category, &b.type_name)));
+        infos
+    }
+
+    /// The node types in the given category, sorted by type name.
+    pub fn function_infos_in_category(&self, category: &str) -> Vec<&FunctionInfo> {
+        self.function_infos()
+            .into_iter()
+            .filter(|info| info.category == category)
+            .collect()
+    }
+
+    /// All categories that have at least one node type, sorted by name.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = self
+            .functions
+            .values()
+            .map(|e| e.info.category.as_str())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+}
@@ -0,0 +1,129 @@
+//! Functions whose output is computed from their inputs one element at a time.
+
+use crate::{Function, FunctionRepository, NetworkError, Node, PortSlice, RenderContext};
+
+/// A kind of value that element-wise functions work on: how its ports are added, and how
+/// values are read from and written to a slice.
+pub trait Element: Sized + 'static {
+    /// The type of the default values of input ports.
+    type Default: Copy + Sync + 'static;
+
+    fn add_input_port(node: &mut Node, name: &str, default: Self::Default);
+    fn add_output_port(node: &mut Node, name: &str);
+    fn get(slice: &PortSlice, index: usize) -> Self;
+    fn to_slice(values: Vec<Self>) -> PortSlice;
+}
+
+impl Element for f32 {
+    type Default = f32;
+
+    fn add_input_port(node: &mut Node, name: &str, default: f32) {
+        node.add_float_input_port(name, vec![default]);
+    }
+
+    fn add_output_port(node: &mut Node, name: &str) {
+        node.add_float_output_port(name);
+    }
+
+    fn get(slice: &PortSlice, index: usize) -> f32 {
+        slice.get_float(index)
+    }
+
+    fn to_slice(values: Vec<f32>) -> PortSlice {
+        PortSlice::new_float(values)
+    }
+}
+
+impl Element for String {
+    type Default = &'static str;
+
+    fn add_input_port(node: &mut Node, name: &str, default: &str) {
+        node.add_string_input_port(name, vec![default]);
+    }
+
+    fn add_output_port(node: &mut Node, name: &str) {
+        node.add_string_output_port(name);
+    }
+
+    fn get(slice: &PortSlice, index: usize) -> String {
+        slice.get_string(index)
+    }
+
+    fn to_slice(values: Vec<String>) -> PortSlice {
+        PortSlice::String(values)
+    }
+}
+
+impl Element for bool {
+    type Default = bool;
+
+    fn add_input_port(node: &mut Node, name: &str, default: bool) {
+        node.add_bool_input_port(name, vec![default]);
+    }
+
+    fn add_output_port(node: &mut Node, name: &str) {
+        node.add_bool_output_port(name);
+    }
+
+    fn get(slice: &PortSlice, index: usize) -> bool {
+        slice.get_bool(index)
+    }
+
+    fn to_slice(values: Vec<bool>) -> PortSlice {
+        PortSlice::new_bool(values)
+    }
+}
+
+/// Computes a value from the values of the inputs, in the order the inputs were declared.
+/// An error message makes the node fail to render.
+pub type ElementOp<T> = fn(&[T]) -> Result<T, String>;
+
+/// A function with inputs of a single kind and an output "out" of the same kind, computed
+/// element-wise using the node's list matching. The inputs are given as names and default
+/// values.
+pub struct ElementWiseFunction<T: Element> {
+    pub ports: &'static [(&'static str, T::Default)],
+    pub op: ElementOp<T>,
+}
+
+impl<T: Element> Function for ElementWiseFunction<T> {
+    fn setup(&self, node: &mut Node) {
+        for (name, default) in self.ports {
+            T::add_input_port(node, name, *default);
+        }
+        T::add_output_port(node, "out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let inputs: Vec<&PortSlice> = (0..self.ports.len())
+            .map(|i| ctx.get_input_slice(node.id, i))
+            .collect();
+        let mut values = Vec::with_capacity(inputs.len());
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            values.clear();
+            for (j, input) in inputs.iter().enumerate() {
+                values.push(T::get(input, list_match.index(i, j)));
+            }
+            let result = (self.op)(&values)
+                .map_err(|message| NetworkError::RenderFailed(node.id, message))?;
+            results.push(result);
+        }
+        ctx.set_output_slice(node.id, 0, T::to_slice(results));
+        Ok(())
+    }
+}
+
+pub(crate) fn register_element_wise<T: Element>(
+    repository: &mut FunctionRepository,
+    type_name: &str,
+    category: &str,
+    description: &str,
+    ports: &'static [(&'static str, T::Default)],
+    op: ElementOp<T>,
+) {
+    repository.register(type_name, category, description, move || {
+        Box::new(ElementWiseFunction { ports, op })
+    });
+}
@@ -0,0 +1,713 @@
+//! Nodes that work on whole lists of any kind.
+//!
+//! The list is a list input, so a node sees all of its items at once. The other inputs are
+//! settings such as a size or a seed. Only the first value of a setting is used: the list nodes
+//! don't do list matching. A nested list is handled one sublist at a time, as for every node.
+
+use super::check_list_size;
+use crate::random::Random;
+use crate::{
+    Color, Function, FunctionRepository, NetworkError, Node, Point, PortKind, PortSlice,
+    RenderContext,
+};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    repository.register(
+        "Flatten",
+        "list",
+        "Combines all sublists into a single list.",
+        || Box::new(FlattenFunction {}),
+    );
+    repository.register(
+        "Group",
+        "list",
+        "Splits a list into sublists of the given size.",
+        || Box::new(GroupFunction {}),
+    );
+    repository.register("Count", "list", "The number of items in a list.", || {
+        Box::new(CountFunction {})
+    });
+    repository.register("First", "list", "The first item of a list.", || {
+        Box::new(SelectFunction {
+            select: |size| (0..size.min(1)).collect(),
+        })
+    });
+    repository.register("Last", "list", "The last item of a list.", || {
+        Box::new(SelectFunction {
+            select: |size| (size.saturating_sub(1)..size).collect(),
+        })
+    });
+    repository.register(
+        "Rest",
+        "list",
+        "All items of a list except the first.",
+        || {
+            Box::new(SelectFunction {
+                select: |size| (1.min(size)..size).collect(),
+            })
+        },
+    );
+    repository.register(
+        "Reverse",
+        "list",
+        "The items of a list in reverse order.",
+        || {
+            Box::new(SelectFunction {
+                select: |size| (0..size).rev().collect(),
+            })
+        },
+    );
+    repository.register(
+        "Slice",
+        "list",
+        "A part of a list, or everything except that part if inverted.",
+        || Box::new(SliceFunction {}),
+    );
+    repository.register(
+        "Shift",
+        "list",
+        "Moves the first items of a list to the end. A negative amount shifts the other way.",
+        || Box::new(ShiftFunction {}),
+    );
+    repository.register(
+        "Repeat",
+        "list",
+        "Repeats a list, or each item of the list, the given amount of times.",
+        || Box::new(RepeatFunction {}),
+    );
+    repository.register(
+        "Sort",
+        "list",
+        "Sorts a list from small to large. Geometry keeps its order.",
+        || Box::new(SortFunction {}),
+    );
+    repository.register(
+        "Shuffle",
+        "list",
+        "Puts the items of a list in a random order. The same seed gives the same order.",
+        || Box::new(ShuffleFunction {}),
+    );
+    repository.register(
+        "Pick",
+        "list",
+        "Picks the given amount of random items from a list. The same seed gives the same items.",
+        || Box::new(PickFunction {}),
+    );
+    repository.register(
+        "Cull",
+        "list",
+        "Keeps the items of a list for which the boolean is true. The booleans repeat.",
+        || Box::new(CullFunction {}),
+    );
+    repository.register(
+        "Distinct",
+        "list",
+        "Removes the items that already appeared earlier in the list.",
+        || Box::new(DistinctFunction {}),
+    );
+    repository.register("Combine", "list", "Joins several lists into one.", || {
+        Box::new(CombineFunction {})
+    });
+    repository.register(
+        "Switch",
+        "list",
+        "Outputs one of the inputs, chosen by index.",
+        || Box::new(SwitchFunction {}),
+    );
+}
+
+/// Reads a setting. The list nodes only use the first value of their value inputs.
+fn get_int(ctx: &RenderContext, node: &Node, port_index: usize) -> i32 {
+    ctx.get_input_slice(node.id, port_index).get_int(0)
+}
+
+fn get_bool(ctx: &RenderContext, node: &Node, port_index: usize) -> bool {
+    ctx.get_input_slice(node.id, port_index).get_bool(0)
+}
+
+pub struct FlattenFunction {}
+impl Function for FlattenFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_tree_input_port("list");
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let flat = in_list.flatten();
+        ctx.set_output_slice(node.id, 0, flat);
+        Ok(())
+    }
+}
+
+pub struct GroupFunction {}
+impl Function for GroupFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("size", vec![2]);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let size = get_int(ctx, node, 1).max(1) as usize;
+        let groups = in_list.group(size);
+        ctx.set_output_slice(node.id, 0, groups);
+        Ok(())
+    }
+}
+
+pub struct CountFunction {}
+impl Function for CountFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_output_port("count");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let count = ctx.get_input_slice(node.id, 0).size() as i32;
+        ctx.set_output_slice(node.id, 0, PortSlice::new_int(vec![count]));
+        Ok(())
+    }
+}
+
+/// Outputs the items of the list at the indices returned by `select` for the size of the list.
+pub struct SelectFunction {
+    pub select: fn(usize) -> Vec<usize>,
+}
+
+impl Function for SelectFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let selected = in_list.select(&(self.select)(in_list.size()));
+        ctx.set_output_slice(node.id, 0, selected);
+        Ok(())
+    }
+}
+
+pub struct SliceFunction {}
+impl Function for SliceFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("start", vec![0]);
+        node.add_int_input_port("size", vec![10]);
+        node.add_bool_input_port("invert", vec![false]);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let len = in_list.size();
+        let start = (get_int(ctx, node, 1).max(0) as usize).min(len);
+        let end = start + (get_int(ctx, node, 2).max(0) as usize).min(len - start);
+        let invert = get_bool(ctx, node, 3);
+        let indices: Vec<usize> = if invert {
+            (0..start).chain(end..len).collect()
+        } else {
+            (start..end).collect()
+        };
+        let sliced = in_list.select(&indices);
+        ctx.set_output_slice(node.id, 0, sliced);
+        Ok(())
+    }
+}
+
+pub struct ShiftFunction {}
+impl Function for ShiftFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("amount", vec![1]);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let len = in_list.size();
+        let indices: Vec<usize> = if len == 0 {
+            Vec::new()
+        } else {
+            let amount = get_int(ctx, node, 1).rem_euclid(len as i32) as usize;
+            (amount..amount + len).collect()
+        };
+        let shifted = in_list.select(&indices);
+        ctx.set_output_slice(node.id, 0, shifted);
+        Ok(())
+    }
+}
+
+pub struct RepeatFunction {}
+impl Function for RepeatFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("amount", vec![1]);
+        node.add_bool_input_port("per_item", vec![false]);
+        node.add_generic_output_port("list");
+    }
+
+    /// Repeating a list into more than `MAX_LIST_SIZE` items fails to render.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let len = in_list.size();
+        let amount = get_int(ctx, node, 1).max(0) as usize;
+        check_list_size(node.id, len as f64 * amount as f64)?;
+        let per_item = get_bool(ctx, node, 2);
+        let indices: Vec<usize> = if per_item {
+            (0..len * amount).map(|i| i / amount).collect()
+        } else {
+            (0..len * amount).map(|i| i % len).collect()
+        };
+        let repeated = in_list.select(&indices);
+        ctx.set_output_slice(node.id, 0, repeated);
+        Ok(())
+    }
+}
+
+/// The indices of the list in sorted order. The sort is stable, so equal items keep their order.
+fn sorted_indices(list: &PortSlice) -> Vec<usize> {
+    fn sort<T>(vals: &[T], compare: impl Fn(&T, &T) -> Ordering) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..vals.len()).collect();
+        indices.sort_by(|&a, &b| compare(&vals[a], &vals[b]));
+        indices
+    }
+    match list {
+        PortSlice::Int(vals) => sort(vals, |a, b| a.cmp(b)),
+        PortSlice::Float(vals) => sort(vals, |a, b| a.total_cmp(b)),
+        PortSlice::String(vals) => sort(vals, |a, b| a.cmp(b)),
+        PortSlice::Bool(vals) => sort(vals, |a, b| a.cmp(b)),
+        PortSlice::Point(vals) => sort(vals, |a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))),
+        PortSlice::Color(vals) => sort(vals, |a, b| {
+            let key = |c: &Color| [c.r, c.g, c.b, c.a];
+            key(a)
+                .iter()
+                .zip(key(b).iter())
+                .map(|(x, y)| x.total_cmp(y))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        }),
+        PortSlice::Geometry(vals) => (0..vals.len()).collect(),
+        PortSlice::List(_, lists) => (0..lists.len()).collect(),
+    }
+}
+
+pub struct SortFunction {}
+impl Function for SortFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let sorted = in_list.select(&sorted_indices(in_list));
+        ctx.set_output_slice(node.id, 0, sorted);
+        Ok(())
+    }
+}
+
+pub struct ShuffleFunction {}
+impl Function for ShuffleFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("seed", vec![0]);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let mut random = Random::new(get_int(ctx, node, 1) as u64);
+        let mut indices: Vec<usize> = (0..in_list.size()).collect();
+        random.shuffle(&mut indices);
+        let shuffled = in_list.select(&indices);
+        ctx.set_output_slice(node.id, 0, shuffled);
+        Ok(())
+    }
+}
+
+pub struct PickFunction {}
+impl Function for PickFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("amount", vec![5]);
+        node.add_int_input_port("seed", vec![0]);
+        node.add_generic_output_port("list");
+    }
+
+    /// Items are picked without repeating, so at most the whole list is returned.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let amount = get_int(ctx, node, 1).max(0) as usize;
+        let mut random = Random::new(get_int(ctx, node, 2) as u64);
+        let mut indices: Vec<usize> = (0..in_list.size()).collect();
+        random.shuffle(&mut indices);
+        indices.truncate(amount);
+        let picked = in_list.select(&indices);
+        ctx.set_output_slice(node.id, 0, picked);
+        Ok(())
+    }
+}
+
+pub struct CullFunction {}
+impl Function for CullFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_list_input_port("booleans", PortKind::Bool);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let culled = cull(
+            ctx.get_input_slice(node.id, 0),
+            ctx.get_input_slice(node.id, 1),
+        );
+        ctx.set_output_slice(node.id, 0, culled);
+        Ok(())
+    }
+}
+
+/// The items of the list for which the boolean at the same index is true. The booleans repeat
+/// if there are fewer of them than items; extra booleans are ignored. Without booleans all
+/// items are kept.
+pub(crate) fn cull(list: &PortSlice, booleans: &PortSlice) -> PortSlice {
+    let indices: Vec<usize> = (0..list.size())
+        .filter(|&i| booleans.size() == 0 || booleans.get_bool(i))
+        .collect();
+    list.select(&indices)
+}
+
+/// A list item that can be hashed and compared for equality. Floats are compared by their bits,
+/// with 0.0 and -0.0 being the same.
+#[derive(PartialEq, Eq, Hash)]
+enum ItemKey<'a> {
+    Int(i32),
+    Float(u32),
+    String(&'a str),
+    Bool(bool),
+    Point(u32, u32),
+    Color([u32; 4]),
+    Geometry(Vec<PathKey>),
+    List(Vec<ItemKey<'a>>),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct PathKey {
+    points: Vec<(u32, u32)>,
+    closed: bool,
+    fill: Option<[u32; 4]>,
+}
+
+fn float_key(v: f32) -> u32 {
+    if v == 0.0 {
+        0
+    } else {
+        v.to_bits()
+    }
+}
+
+fn point_key(p: &Point) -> (u32, u32) {
+    (float_key(p.x), float_key(p.y))
+}
+
+fn color_key(c: &Color) -> [u32; 4] {
+    [c.r, c.g, c.b, c.a].map(float_key)
+}
+
+fn item_keys(list: &PortSlice) -> Vec<ItemKey<'_>> {
+    match list {
+        PortSlice::Int(vals) => vals.iter().map(|v| ItemKey::Int(*v)).collect(),
+        PortSlice::Float(vals) => vals.iter().map(|v| ItemKey::Float(float_key(*v))).collect(),
+        PortSlice::String(vals) => vals.iter().map(|v| ItemKey::String(v)).collect(),
+        PortSlice::Bool(vals) => vals.iter().map(|v| ItemKey::Bool(*v)).collect(),
+        PortSlice::Point(vals) => vals
+            .iter()
+            .map(|p| {
+                let (x, y) = point_key(p);
+                ItemKey::Point(x, y)
+            })
+            .collect(),
+        PortSlice::Color(vals) => vals.iter().map(|c| ItemKey::Color(color_key(c))).collect(),
+        PortSlice::Geometry(vals) => vals
+            .iter()
+            .map(|geometry| {
+                let paths = geometry.paths.iter().map(|path| PathKey {
+                    points: path.points.iter().map(point_key).collect(),
+                    closed: path.closed,
+                    fill: path.fill.as_ref().map(color_key),
+                });
+                ItemKey::Geometry(paths.collect())
+            })
+            .collect(),
+        PortSlice::List(_, lists) => lists
+            .iter()
+            .map(|list| ItemKey::List(item_keys(list)))
+            .collect(),
+    }
+}
+
+/// The indices of the first occurrence of every item.
+fn distinct_indices(list: &PortSlice) -> Vec<usize> {
+    let keys = item_keys(list);
+    let mut seen = HashSet::with_capacity(keys.len());
+    (0..keys.len()).filter(|&i| seen.insert(&keys[i])).collect()
+}
+
+pub struct DistinctFunction {}
+impl Function for DistinctFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let distinct = in_list.select(&distinct_indices(in_list));
+        ctx.set_output_slice(node.id, 0, distinct);
+        Ok(())
+    }
+}
+
+pub struct CombineFunction {}
+impl Function for CombineFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list1");
+        node.add_generic_list_input_port("list2");
+        node.add_generic_list_input_port("list3");
+        node.add_generic_output_port("list");
+    }
+
+    /// The result has the kind of the first list that is not empty. The other lists are
+    /// converted to that kind.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let lists: Vec<&PortSlice> = (0..3).map(|i| ctx.get_input_slice(node.id, i)).collect();
+        let kind = lists
+            .iter()
+            .find(|l| l.size() > 0)
+            .map_or(lists[0].kind(), |l| l.kind());
+        let mut combined = PortSlice::new_empty(kind);
+        for list in lists {
+            combined.append(list);
+        }
+        ctx.set_output_slice(node.id, 0, combined);
+        Ok(())
+    }
+}
+
+pub struct SwitchFunction {}
+impl Function for SwitchFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("input1");
+        node.add_generic_list_input_port("input2");
+        node.add_int_input_port("index", vec![0]);
+        node.add_generic_output_port("output");
+    }
+
+    /// The index wraps around, so 2 picks the first input again.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let index = get_int(ctx, node, 2).rem_euclid(2) as usize;
+        let selected = ctx.get_input_shared(node.id, index);
+        ctx.set_output_shared(node.id, 0, selected);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_util::{render_single_node, render_type, test_node};
+    use super::*;
+    use crate::{Geometry, Path};
+
+    fn strings(values: Vec<&str>) -> PortSlice {
+        PortSlice::new_string(values)
+    }
+
+    fn abcd() -> PortSlice {
+        strings(vec!["a", "b", "c", "d"])
+    }
+
+    fn int(v: i32) -> PortSlice {
+        PortSlice::new_int(vec![v])
+    }
+
+    #[test]
+    fn select() {
+        let list = |type_name: &str, list: PortSlice| render_type(type_name, vec![("list", list)]);
+        assert_eq!(list("Count", abcd()), PortSlice::new_int(vec![4]));
+        assert_eq!(list("First", abcd()), strings(vec!["a"]));
+        assert_eq!(list("Last", abcd()), strings(vec!["d"]));
+        assert_eq!(list("Rest", abcd()), strings(vec!["b", "c", "d"]));
+        assert_eq!(list("Reverse", abcd()), strings(vec!["d", "c", "b", "a"]));
+        assert_eq!(
+            list("First", PortSlice::new_empty(PortKind::Float)).size(),
+            0
+        );
+        assert_eq!(
+            render_type(
+                "Slice",
+                vec![("list", abcd()), ("start", int(1)), ("size", int(2))]
+            ),
+            strings(vec!["b", "c"])
+        );
+        assert_eq!(
+            render_type(
+                "Slice",
+                vec![
+                    ("list", abcd()),
+                    ("start", int(1)),
+                    ("size", int(2)),
+                    ("invert", PortSlice::new_bool(vec![true]))
+                ]
+            ),
+            strings(vec!["a", "d"])
+        );
+        assert_eq!(
+            render_type("Shift", vec![("list", abcd()), ("amount", int(-1))]),
+            strings(vec!["d", "a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn repeat() {
+        let ab = || strings(vec!["a", "b"]);
+        assert_eq!(
+            render_type("Repeat", vec![("list", ab()), ("amount", int(2))]),
+            strings(vec!["a", "b", "a", "b"])
+        );
+        assert_eq!(
+            render_type(
+                "Repeat",
+                vec![
+                    ("list", ab()),
+                    ("amount", int(2)),
+                    ("per_item", PortSlice::new_bool(vec![true]))
+                ]
+            ),
+            strings(vec!["a", "a", "b", "b"])
+        );
+        let mut node = test_node("Repeat");
+        node.inputs[0].slice = abcd();
+        node.set_int("amount", 0, i32::MAX);
+        assert!(matches!(
+            render_single_node(node, 0),
+            Err(NetworkError::RenderFailed(1, _))
+        ));
+    }
+
+    #[test]
+    fn sort_and_distinct() {
+        let list = |type_name: &str, list: PortSlice| render_type(type_name, vec![("list", list)]);
+        assert_eq!(
+            list("Sort", PortSlice::new_float(vec![3.0, -1.0, 2.0])),
+            PortSlice::new_float(vec![-1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            list("Distinct", strings(vec!["a", "b", "a", "c", "b"])),
+            strings(vec!["a", "b", "c"])
+        );
+        assert_eq!(
+            list(
+                "Distinct",
+                PortSlice::new_float(vec![0.0, 1.5, -0.0, 1.5, 2.0])
+            ),
+            PortSlice::new_float(vec![0.0, 1.5, 2.0])
+        );
+        let points = PortSlice::new_point(vec![
+            Point::new(1.0, 2.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 2.0),
+        ]);
+        assert_eq!(list("Distinct", points).size(), 2);
+        // Sublists are handled one at a time.
+        let nested = PortSlice::new_int(vec![1, 1, 2, 2]).group(2);
+        assert_eq!(
+            list("Distinct", nested),
+            PortSlice::new_int(vec![1, 2]).group(1)
+        );
+        let square = |closed: bool| {
+            let points = vec![
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 0.0),
+                Point::new(1.0, 1.0),
+            ];
+            Geometry::new(vec![Path::new(points, closed)])
+        };
+        let shapes = PortSlice::Geometry(vec![square(true), square(false), square(true)]);
+        assert_eq!(
+            list("Distinct", shapes),
+            PortSlice::Geometry(vec![square(true), square(false)])
+        );
+        // Large lists are fast enough.
+        let many = PortSlice::new_int((0..200_000).map(|i| i % 10_000).collect());
+        assert_eq!(
+            list("Distinct", many),
+            PortSlice::new_int((0..10_000).collect())
+        );
+    }
+
+    #[test]
+    fn distinct_sublists() {
+        let lists = PortSlice::new_int(vec![1, 2, 1, 3]).group(1);
+        assert_eq!(distinct_indices(&lists), vec![0, 1, 3]);
+        let lists = PortSlice::new_float(vec![0.0, 1.0, -0.0, 1.0]).group(2);
+        assert_eq!(distinct_indices(&lists), vec![0]);
+    }
+
+    #[test]
+    fn shuffle_and_pick() {
+        let numbers = PortSlice::new_int((0..20).collect());
+        let shuffle = |seed: i32| {
+            render_type(
+                "Shuffle",
+                vec![("list", numbers.clone()), ("seed", int(seed))],
+            )
+        };
+        let shuffled = shuffle(5);
+        assert_ne!(shuffled, numbers);
+        assert_eq!(shuffle(5), shuffled);
+        assert_ne!(shuffle(6), shuffled);
+        assert_eq!(render_type("Sort", vec![("list", shuffled)]), numbers);
+
+        let picked = render_type(
+            "Pick",
+            vec![
+                ("list", numbers.clone()),
+                ("amount", int(5)),
+                ("seed", int(1)),
+            ],
+        );
+        assert_eq!(picked.size(), 5);
+        assert_eq!(render_type("Distinct", vec![("list", picked)]).size(), 5);
+        let picked = render_type("Pick", vec![("list", numbers), ("amount", int(50))]);
+        assert_eq!(picked.size(), 20);
+    }
+
+    #[test]
+    fn cull_combine_switch() {
+        let mut cull = test_node("Cull");
+        cull.inputs[0].slice = PortSlice::new_float(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        cull.inputs[1].slice = PortSlice::new_bool(vec![true, false]);
+        assert_eq!(
+            render_single_node(cull, 0).unwrap(),
+            PortSlice::new_float(vec![1.0, 3.0, 5.0])
+        );
+
+        let mut combine = test_node("Combine");
+        combine.inputs[1].slice = strings(vec!["1", "2"]);
+        combine.inputs[2].slice = PortSlice::new_int(vec![3]);
+        assert_eq!(
+            render_single_node(combine, 0).unwrap(),
+            strings(vec!["1", "2", "3"])
+        );
+
+        let mut switch = test_node("Switch");
+        switch.inputs[0].slice = PortSlice::new_float(vec![1.0]);
+        switch.inputs[1].slice = strings(vec!["b"]);
+        switch.set_int("index", 0, 3);
+        assert_eq!(render_single_node(switch, 0).unwrap(), strings(vec!["b"]));
+    }
+}
@@ -0,0 +1,245 @@
+use super::list::cull;
+use super::{register_element_wise, ElementOp, ElementWiseFunction};
+use crate::{Function, FunctionRepository, NetworkError, Node, PortSlice, RenderContext};
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    repository.register(
+        "Compare",
+        "logic",
+        "Compares two numbers with <, <=, ==, !=, >= or >.",
+        || Box::new(CompareFunction {}),
+    );
+    register_logic(repository, "And", "True if both are true.", AB, |v| {
+        Ok(v[0] && v[1])
+    });
+    register_logic(repository, "Or", "True if either is true.", AB, |v| {
+        Ok(v[0] || v[1])
+    });
+    register_logic(
+        repository,
+        "Xor",
+        "True if exactly one of them is true.",
+        AB,
+        |v| Ok(v[0] != v[1]),
+    );
+    register_logic(
+        repository,
+        "Not",
+        "Inverts a boolean.",
+        &[("v", false)],
+        |v| Ok(!v[0]),
+    );
+    repository.register(
+        "If",
+        "logic",
+        "Picks the value of then or else for every condition.",
+        || Box::new(IfFunction {}),
+    );
+    repository.register(
+        "Filter",
+        "logic",
+        "Keeps the items of a list for which keep is true. Like Cull, but keep is a single value by default.",
+        || Box::new(FilterFunction {}),
+    );
+}
+
+pub type LogicOp = ElementOp<bool>;
+pub type LogicFunction = ElementWiseFunction<bool>;
+
+const AB: &[(&str, bool)] = &[("a", false), ("b", false)];
+
+fn register_logic(
+    repository: &mut FunctionRepository,
+    type_name: &str,
+    description: &str,
+    ports: &'static [(&'static str, bool)],
+    op: LogicOp,
+) {
+    register_element_wise(repository, type_name, "logic", description, ports, op);
+}
+
+pub struct CompareFunction {}
+impl Function for CompareFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_float_input_port("a", vec![0.0]);
+        node.add_float_input_port("b", vec![0.0]);
+        node.add_string_input_port("comparison", vec!["<"]);
+        node.add_bool_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let a = ctx
+                .get_input_slice(node.id, 0)
+                .get_float(list_match.index(i, 0));
+            let b = ctx
+                .get_input_slice(node.id, 1)
+                .get_float(list_match.index(i, 1));
+            let comparison = ctx
+                .get_input_slice(node.id, 2)
+                .get_string(list_match.index(i, 2));
+            let result = match comparison.trim() {
+                "<" => a < b,
+                "<=" => a <= b,
+                "==" => a == b,
+                "!=" => a != b,
+                ">=" => a >= b,
+                ">" => a > b,
+                _ => {
+                    return Err(NetworkError::RenderFailed(
+                        node.id,
+                        format!("Unknown comparison \"{}\".", comparison),
+                    ));
+                }
+            };
+            results.push(result);
+        }
+        ctx.set_output_slice(node.id, 0, PortSlice::new_bool(results));
+        Ok(())
+    }
+}
+
+pub struct IfFunction {}
+impl Function for IfFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_bool_input_port("condition", vec![true]);
+        node.add_generic_input_port("then");
+        node.add_generic_input_port("else");
+        node.add_generic_output_port("out");
+    }
+
+    /// The output has the kind of then. Values of else are converted to it.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let condition = ctx.get_input_slice(node.id, 0);
+        let then = ctx.get_input_slice(node.id, 1);
+        let otherwise = ctx.get_input_slice(node.id, 2);
+        let mut results = PortSlice::new_empty(then.kind());
+        for i in 0..list_match.len() {
+            let value = if condition.get_bool(list_match.index(i, 0)) {
+                then.select(&[list_match.index(i, 1)])
+            } else {
+                otherwise.select(&[list_match.index(i, 2)])
+            };
+            results.append(&value);
+        }
+        ctx.set_output_slice(node.id, 0, results);
+        Ok(())
+    }
+}
+
+pub struct FilterFunction {}
+impl Function for FilterFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_bool_input_port("keep", vec![true]);
+        node.add_generic_output_port("list");
+    }
+
+    /// Every item of the list is kept or dropped once. If keep is shorter than the list it
+    /// repeats; extra values of keep are ignored.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let filtered = cull(
+            ctx.get_input_slice(node.id, 0),
+            ctx.get_input_slice(node.id, 1),
+        );
+        ctx.set_output_slice(node.id, 0, filtered);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_util::{render_single_node, render_type, test_node};
+    use super::*;
+
+    fn bools(values: Vec<bool>) -> PortSlice {
+        PortSlice::new_bool(values)
+    }
+
+    #[test]
+    fn compare() {
+        let compare = |comparison: &str| {
+            render_type(
+                "Compare",
+                vec![
+                    ("a", PortSlice::new_float(vec![1.0, 2.0, 3.0])),
+                    ("b", PortSlice::new_float(vec![2.0])),
+                    ("comparison", PortSlice::new_string(vec![comparison])),
+                ],
+            )
+        };
+        assert_eq!(compare("<"), bools(vec![true, false, false]));
+        assert_eq!(compare("<="), bools(vec![true, true, false]));
+        assert_eq!(compare("=="), bools(vec![false, true, false]));
+        assert_eq!(compare("!="), bools(vec![true, false, true]));
+        assert_eq!(compare(">="), bools(vec![false, true, true]));
+        assert_eq!(compare(">"), bools(vec![false, false, true]));
+
+        let mut node = test_node("Compare");
+        node.set_string("comparison", 0, "<>");
+        match render_single_node(node, 0) {
+            Err(NetworkError::RenderFailed(1, _)) => {}
+            other => panic!("Expected a render error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logic() {
+        let a = || bools(vec![false, false, true, true]);
+        let b = || bools(vec![false, true, false, true]);
+        let logic = |type_name: &str| render_type(type_name, vec![("a", a()), ("b", b())]);
+        assert_eq!(logic("And"), bools(vec![false, false, false, true]));
+        assert_eq!(logic("Or"), bools(vec![false, true, true, true]));
+        assert_eq!(logic("Xor"), bools(vec![false, true, true, false]));
+        assert_eq!(
+            render_type("Not", vec![("v", a())]),
+            bools(vec![true, true, false, false])
+        );
+    }
+
+    #[test]
+    fn if_and_filter() {
+        let conditions = || bools(vec![true, false, true]);
+        assert_eq!(
+            render_type(
+                "If",
+                vec![
+                    ("condition", conditions()),
+                    ("then", PortSlice::new_string(vec!["a", "b", "c"])),
+                    ("else", PortSlice::new_int(vec![0])),
+                ],
+            ),
+            PortSlice::new_string(vec!["a", "0", "c"])
+        );
+        assert_eq!(
+            render_type(
+                "Filter",
+                vec![
+                    ("list", PortSlice::new_float(vec![1.0, 2.0, 3.0])),
+                    ("keep", conditions()),
+                ],
+            ),
+            PortSlice::new_float(vec![1.0, 3.0])
+        );
+        let filter = |keep: Vec<bool>| {
+            render_type(
+                "Filter",
+                vec![
+                    ("list", PortSlice::new_float(vec![1.0, 2.0])),
+                    ("keep", bools(keep)),
+                ],
+            )
+        };
+        // Extra values of keep are ignored, so no items are repeated.
+        assert_eq!(
+            filter(vec![true, true, true, false]),
+            PortSlice::new_float(vec![1.0, 2.0])
+        );
+        // A shorter keep repeats.
+        assert_eq!(filter(vec![false]), PortSlice::new_float(vec![]));
+        assert_eq!(filter(vec![true]), PortSlice::new_float(vec![1.0, 2.0]));
+    }
+}
@@ -1,4 +1,203 @@
+use super::{
+    check_list_size, register_element_wise, set_output_lists, ElementOp, ElementWiseFunction,
+};
 pub use crate::{Function, Node, RenderContext};
+use crate::{FunctionRepository, NetworkError, PortKind, PortSlice};
+use std::f32::consts::PI;
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    repository.register("Value", "math", "Outputs the given number.", || {
+        Box::new(ValueFunction {})
+    });
+    repository.register("Add", "math", "Adds two numbers.", || {
+        Box::new(AddFunction {})
+    });
+    register_math(
+        repository,
+        "Subtract",
+        "Subtracts the second number from the first.",
+        AB,
+        |v| Ok(v[0] - v[1]),
+    );
+    register_math(
+        repository,
+        "Multiply",
+        "Multiplies two numbers.",
+        &[("a", 0.0), ("b", 1.0)],
+        |v| Ok(v[0] * v[1]),
+    );
+    register_math(
+        repository,
+        "Divide",
+        "Divides the first number by the second.",
+        &[("a", 0.0), ("b", 1.0)],
+        |v| {
+            if v[1] == 0.0 {
+                return Err("Division by zero.".to_owned());
+            }
+            Ok(v[0] / v[1])
+        },
+    );
+    register_math(
+        repository,
+        "Modulo",
+        "The remainder of dividing a by b. It has the same sign as b.",
+        &[("a", 0.0), ("b", 1.0)],
+        |v| {
+            if v[1] == 0.0 {
+                return Err("Division by zero.".to_owned());
+            }
+            Ok(v[0] - v[1] * (v[0] / v[1]).floor())
+        },
+    );
+    register_math(repository, "Negate", "Negates a number.", V, |v| Ok(-v[0]));
+    register_math(
+        repository,
+        "Abs",
+        "The absolute value of a number.",
+        V,
+        |v| Ok(v[0].abs()),
+    );
+    register_math(repository, "Sqrt", "The square root of a number.", V, |v| {
+        if v[0] < 0.0 {
+            return Err(format!("Can't take the square root of {}.", v[0]));
+        }
+        Ok(v[0].sqrt())
+    });
+    register_math(
+        repository,
+        "Pow",
+        "Raises a number to the given power.",
+        &[("v", 0.0), ("exponent", 2.0)],
+        |v| Ok(v[0].powf(v[1])),
+    );
+    register_math(
+        repository,
+        "Log",
+        "The natural logarithm of a number.",
+        &[("v", 1.0)],
+        |v| {
+            if v[0] <= 0.0 {
+                return Err(format!("Can't take the logarithm of {}.", v[0]));
+            }
+            Ok(v[0].ln())
+        },
+    );
+    register_math(
+        repository,
+        "Round",
+        "Rounds a number to the nearest integer. Halfway cases round away from zero.",
+        V,
+        |v| Ok(v[0].round()),
+    );
+    register_math(repository, "Floor", "Rounds a number down.", V, |v| {
+        Ok(v[0].floor())
+    });
+    register_math(repository, "Ceil", "Rounds a number up.", V, |v| {
+        Ok(v[0].ceil())
+    });
+    register_math(repository, "Min", "The smallest of two numbers.", AB, |v| {
+        Ok(v[0].min(v[1]))
+    });
+    register_math(repository, "Max", "The largest of two numbers.", AB, |v| {
+        Ok(v[0].max(v[1]))
+    });
+    register_math(
+        repository,
+        "Clamp",
+        "Limits a number to the given range.",
+        &[("v", 0.0), ("min", 0.0), ("max", 1.0)],
+        |v| Ok(v[0].max(v[1]).min(v[2])),
+    );
+    register_math(
+        repository,
+        "Sin",
+        "The sine of an angle in radians.",
+        V,
+        |v| Ok(v[0].sin()),
+    );
+    register_math(
+        repository,
+        "Cos",
+        "The cosine of an angle in radians.",
+        V,
+        |v| Ok(v[0].cos()),
+    );
+    register_math(
+        repository,
+        "Tan",
+        "The tangent of an angle in radians.",
+        V,
+        |v| Ok(v[0].tan()),
+    );
+    register_math(
+        repository,
+        "Atan2",
+        "The angle in radians of the point (x, y), between -pi and pi.",
+        &[("y", 0.0), ("x", 1.0)],
+        |v| Ok(v[0].atan2(v[1])),
+    );
+    repository.register(
+        "Convert Range",
+        "math",
+        "Maps a number from one range to another.",
+        || Box::new(ConvertRangeFunction {}),
+    );
+    repository.register("Sum", "math", "Adds all numbers in a list.", || {
+        Box::new(SumFunction {})
+    });
+    repository.register(
+        "Range",
+        "math",
+        "Generates numbers from start up to end, in steps.",
+        || Box::new(RangeFunction {}),
+    );
+    repository.register(
+        "Sample",
+        "math",
+        "Generates the given amount of numbers evenly spread between start and end.",
+        || Box::new(SampleFunction {}),
+    );
+    repository.register(
+        "Wave",
+        "math",
+        "Computes the value of a sine, square, triangle or sawtooth wave at the given offset.",
+        || Box::new(WaveFunction {}),
+    );
+}
+
+pub type MathOp = ElementOp<f32>;
+pub type MathFunction = ElementWiseFunction<f32>;
+
+const V: &[(&str, f32)] = &[("v", 0.0)];
+const AB: &[(&str, f32)] = &[("a", 0.0), ("b", 0.0)];
+
+fn register_math(
+    repository: &mut FunctionRepository,
+    type_name: &str,
+    description: &str,
+    ports: &'static [(&'static str, f32)],
+    op: MathOp,
+) {
+    register_element_wise(repository, type_name, "math", description, ports, op);
+}
+
+fn add(v: &[f32]) -> Result<f32, String> {
+    Ok(v[0] + v[1])
+}
+
+const ADD: MathFunction = MathFunction { ports: AB, op: add };
+
+pub struct AddFunction {}
+impl Function for AddFunction {
+    fn setup(&self, node: &mut Node) {
+        ADD.setup(node);
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        ADD.render(node, ctx)
+    }
+}
 
 pub struct ValueFunction {}
 impl Function for ValueFunction {
@@ -7,51 +206,400 @@ impl Function for ValueFunction {
         node.add_float_output_port("out");
     }
 
-    fn render(&self, node: &Node, ctx: &mut RenderContext) {
-        let in_v = ctx.get_input_slice(node.id, 0);
-        ctx.set_output_slice(node.id, 0, in_v.clone());
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_v = ctx.get_input_shared(node.id, 0);
+        ctx.set_output_shared(node.id, 0, in_v);
+        Ok(())
     }
 }
 
-pub struct AddFunction {}
-impl Function for AddFunction {
+pub struct ConvertRangeFunction {}
+impl Function for ConvertRangeFunction {
     fn setup(&self, node: &mut Node) {
-        node.add_float_input_port("a", vec![0.0]);
-        node.add_float_input_port("b", vec![0.0]);
+        node.add_float_input_port("v", vec![0.5]);
+        node.add_float_input_port("source_start", vec![0.0]);
+        node.add_float_input_port("source_end", vec![1.0]);
+        node.add_float_input_port("target_start", vec![0.0]);
+        node.add_float_input_port("target_end", vec![100.0]);
+        node.add_string_input_port("overflow", vec!["ignore"]);
         node.add_float_output_port("out");
     }
 
-    fn render(&self, node: &Node, ctx: &mut RenderContext) {
-        let max_size = ctx.get_max_input_size(node.id);
-        let mut results = Vec::with_capacity(max_size);
-        let in_a = ctx.get_input_slice(node.id, 0);
-        let in_b = ctx.get_input_slice(node.id, 1);
-        for i in 0..max_size {
-            let a = in_a.get_float(i);
-            let b = in_b.get_float(i);
-            results.push(a + b);
+    /// Values outside the source range are left as they are ("ignore"), wrapped around ("wrap"),
+    /// mirrored back into the range ("mirror") or clamped to it ("clamp").
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let input = |j: usize| ctx.get_input_slice(node.id, j);
+            let v = input(0).get_float(list_match.index(i, 0));
+            let source_start = input(1).get_float(list_match.index(i, 1));
+            let source_end = input(2).get_float(list_match.index(i, 2));
+            let target_start = input(3).get_float(list_match.index(i, 3));
+            let target_end = input(4).get_float(list_match.index(i, 4));
+            let overflow = input(5).get_string(list_match.index(i, 5));
+            let t = if source_start == source_end {
+                0.0
+            } else {
+                (v - source_start) / (source_end - source_start)
+            };
+            let t = match overflow.as_str() {
+                "ignore" => t,
+                "wrap" => t.rem_euclid(1.0),
+                "mirror" => {
+                    let t = t.rem_euclid(2.0);
+                    if t > 1.0 {
+                        2.0 - t
+                    } else {
+                        t
+                    }
+                }
+                "clamp" => t.clamp(0.0, 1.0),
+                _ => {
+                    return Err(NetworkError::RenderFailed(
+                        node.id,
+                        format!("Unknown overflow method \"{}\".", overflow),
+                    ));
+                }
+            };
+            results.push(target_start + t * (target_end - target_start));
         }
         ctx.set_output_floats(node.id, 0, results);
+        Ok(())
+    }
+}
+
+pub struct SumFunction {}
+impl Function for SumFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_list_input_port("values", PortKind::Float);
+        node.add_float_output_port("sum");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_values = ctx.get_input_slice(node.id, 0);
+        let sum = (0..in_values.size()).map(|i| in_values.get_float(i)).sum();
+        ctx.set_output_floats(node.id, 0, vec![sum]);
+        Ok(())
+    }
+}
+
+pub struct RangeFunction {}
+impl Function for RangeFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_float_input_port("start", vec![0.0]);
+        node.add_float_input_port("end", vec![10.0]);
+        node.add_float_input_port("step", vec![1.0]);
+        node.add_float_output_port("out");
+    }
+
+    /// The end is not included. A negative step counts down; a step of zero gives an empty list.
+    /// Ranges with more than `MAX_LIST_SIZE` numbers, or without an end, fail to render.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let start = ctx
+                .get_input_slice(node.id, 0)
+                .get_float(list_match.index(i, 0));
+            let end = ctx
+                .get_input_slice(node.id, 1)
+                .get_float(list_match.index(i, 1));
+            let step = ctx
+                .get_input_slice(node.id, 2)
+                .get_float(list_match.index(i, 2));
+            let count = if step == 0.0 {
+                0
+            } else {
+                check_list_size(node.id, ((end as f64 - start as f64) / step as f64).ceil())?
+            };
+            lists.push(PortSlice::new_float(
+                (0..count).map(|j| start + j as f32 * step).collect(),
+            ));
+        }
+        set_output_lists(ctx, node.id, PortKind::Float, lists);
+        Ok(())
     }
 }
 
-pub struct ParseFloatsFunction {}
-impl Function for ParseFloatsFunction {
+pub struct SampleFunction {}
+impl Function for SampleFunction {
     fn setup(&self, node: &mut Node) {
-        node.add_string_input_port("s", vec!["1;2;3;4;5"]);
+        node.add_int_input_port("amount", vec![10]);
+        node.add_float_input_port("start", vec![0.0]);
+        node.add_float_input_port("end", vec![100.0]);
         node.add_float_output_port("out");
     }
 
-    fn render(&self, node: &Node, ctx: &mut RenderContext) {
-        let max_size = ctx.get_max_input_size(node.id);
-        assert_eq!(max_size, 1); // FIXME: support more than one string and combine them.
-        let in_s = ctx.get_input_slice(node.id, 0);
-        let s = in_s.get_string(0);
-        let mut results = Vec::new();
-        for part in s.split(';') {
-            let v = part.parse::<f32>().unwrap();
-            results.push(v);
+    /// Both start and end are included. A single sample is placed at the start. Amounts over
+    /// `MAX_LIST_SIZE` fail to render.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let amount = ctx
+                .get_input_slice(node.id, 0)
+                .get_int(list_match.index(i, 0));
+            let start = ctx
+                .get_input_slice(node.id, 1)
+                .get_float(list_match.index(i, 1));
+            let end = ctx
+                .get_input_slice(node.id, 2)
+                .get_float(list_match.index(i, 2));
+            let amount = check_list_size(node.id, amount as f64)?;
+            let step = if amount > 1 {
+                (end - start) / (amount - 1) as f32
+            } else {
+                0.0
+            };
+            lists.push(PortSlice::new_float(
+                (0..amount).map(|j| start + j as f32 * step).collect(),
+            ));
+        }
+        set_output_lists(ctx, node.id, PortKind::Float, lists);
+        Ok(())
+    }
+}
+
+pub struct WaveFunction {}
+impl Function for WaveFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_float_input_port("min", vec![0.0]);
+        node.add_float_input_port("max", vec![100.0]);
+        node.add_float_input_port("period", vec![60.0]);
+        node.add_float_input_port("offset", vec![0.0]);
+        node.add_string_input_port("type", vec!["sine"]);
+        node.add_float_output_port("out");
+    }
+
+    /// All waves start at the beginning of their period at offset 0. The sine wave starts
+    /// halfway between min and max and rises; the others start at min.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let input = |j: usize| ctx.get_input_slice(node.id, j);
+            let min = input(0).get_float(list_match.index(i, 0));
+            let max = input(1).get_float(list_match.index(i, 1));
+            let period = input(2).get_float(list_match.index(i, 2));
+            let offset = input(3).get_float(list_match.index(i, 3));
+            let wave_type = input(4).get_string(list_match.index(i, 4));
+            if period == 0.0 {
+                return Err(NetworkError::RenderFailed(
+                    node.id,
+                    "The period can't be zero.".to_owned(),
+                ));
+            }
+            let phase = (offset / period).rem_euclid(1.0);
+            let t = match wave_type.as_str() {
+                "sine" => 0.5 + 0.5 * (phase * 2.0 * PI).sin(),
+                "square" => {
+                    if phase < 0.5 {
+                        0.0
+                    } else {
+                        1.0
+                    }
+                }
+                "triangle" => 1.0 - (2.0 * phase - 1.0).abs(),
+                "sawtooth" => phase,
+                _ => {
+                    return Err(NetworkError::RenderFailed(
+                        node.id,
+                        format!("Unknown wave type \"{}\".", wave_type),
+                    ));
+                }
+            };
+            results.push(min + t * (max - min));
         }
         ctx.set_output_floats(node.id, 0, results);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_util::{render_single_node, render_type, test_node};
+    use super::*;
+
+    fn floats(values: Vec<f32>) -> PortSlice {
+        PortSlice::new_float(values)
+    }
+
+    #[test]
+    fn binary() {
+        let ab = |a: Vec<f32>, b: Vec<f32>| vec![("a", floats(a)), ("b", floats(b))];
+        assert_eq!(
+            render_type("Add", ab(vec![3.0, 300.0], vec![5.0, 500.0])),
+            floats(vec![8.0, 800.0])
+        );
+        assert_eq!(
+            render_type("Subtract", ab(vec![5.0, 1.0], vec![2.0])),
+            floats(vec![3.0, -1.0])
+        );
+        assert_eq!(
+            render_type("Multiply", ab(vec![3.0], vec![4.0])),
+            floats(vec![12.0])
+        );
+        assert_eq!(
+            render_type("Divide", ab(vec![1.0], vec![4.0])),
+            floats(vec![0.25])
+        );
+        assert_eq!(
+            render_type("Modulo", ab(vec![7.0, -1.0], vec![3.0])),
+            floats(vec![1.0, 2.0])
+        );
+        assert_eq!(
+            render_type("Min", ab(vec![1.0, 5.0], vec![3.0])),
+            floats(vec![1.0, 3.0])
+        );
+        assert_eq!(
+            render_type("Max", ab(vec![1.0, 5.0], vec![3.0])),
+            floats(vec![3.0, 5.0])
+        );
+        assert_eq!(
+            render_type(
+                "Pow",
+                vec![
+                    ("v", floats(vec![2.0, 3.0])),
+                    ("exponent", floats(vec![3.0]))
+                ]
+            ),
+            floats(vec![8.0, 27.0])
+        );
+        assert_eq!(
+            render_type(
+                "Atan2",
+                vec![("y", floats(vec![1.0])), ("x", floats(vec![0.0]))]
+            ),
+            floats(vec![std::f32::consts::FRAC_PI_2])
+        );
+        assert_eq!(
+            render_type("Clamp", vec![("v", floats(vec![-1.0, 0.5, 2.0]))]),
+            floats(vec![0.0, 0.5, 1.0])
+        );
+
+        let mut node = test_node("Divide");
+        node.set_float("b", 0, 0.0);
+        assert_eq!(
+            render_single_node(node, 0).unwrap_err(),
+            NetworkError::RenderFailed(1, "Division by zero.".to_owned())
+        );
+    }
+
+    #[test]
+    fn unary() {
+        let unary =
+            |type_name: &str, values: Vec<f32>| render_type(type_name, vec![("v", floats(values))]);
+        assert_eq!(unary("Negate", vec![1.0, -2.0]), floats(vec![-1.0, 2.0]));
+        assert_eq!(unary("Abs", vec![-3.0]), floats(vec![3.0]));
+        assert_eq!(unary("Sqrt", vec![16.0]), floats(vec![4.0]));
+        assert_eq!(unary("Log", vec![1.0]), floats(vec![0.0]));
+        assert_eq!(
+            unary("Round", vec![1.5, -1.5, 1.4]),
+            floats(vec![2.0, -2.0, 1.0])
+        );
+        assert_eq!(unary("Floor", vec![1.7, -1.2]), floats(vec![1.0, -2.0]));
+        assert_eq!(unary("Ceil", vec![1.2, -1.7]), floats(vec![2.0, -1.0]));
+        assert_eq!(unary("Sin", vec![0.0]), floats(vec![0.0]));
+        assert_eq!(unary("Cos", vec![0.0]), floats(vec![1.0]));
+        assert_eq!(unary("Tan", vec![0.0]), floats(vec![0.0]));
+
+        let mut node = test_node("Sqrt");
+        node.set_float("v", 0, -4.0);
+        assert!(render_single_node(node, 0).is_err());
+    }
+
+    #[test]
+    fn convert_range() {
+        let convert = |v: f32, overflow: &str| {
+            let mut node = test_node("Convert Range");
+            node.set_float("v", 0, v);
+            node.set_string("overflow", 0, overflow);
+            render_single_node(node, 0).unwrap().get_float(0)
+        };
+        assert_eq!(convert(0.25, "ignore"), 25.0);
+        assert_eq!(convert(1.5, "ignore"), 150.0);
+        assert_eq!(convert(1.25, "wrap"), 25.0);
+        assert_eq!(convert(1.25, "mirror"), 75.0);
+        assert_eq!(convert(-1.0, "clamp"), 0.0);
+    }
+
+    #[test]
+    fn range_and_sample() {
+        assert_eq!(
+            render_type("Range", vec![]),
+            floats((0..10).map(|i| i as f32).collect())
+        );
+        assert_eq!(
+            render_type(
+                "Range",
+                vec![
+                    ("start", floats(vec![10.0])),
+                    ("end", floats(vec![0.0])),
+                    ("step", floats(vec![-4.0]))
+                ]
+            ),
+            floats(vec![10.0, 6.0, 2.0])
+        );
+        assert_eq!(
+            render_type("Range", vec![("step", floats(vec![0.0]))]),
+            floats(vec![])
+        );
+        assert_eq!(
+            render_type("Sample", vec![("end", floats(vec![1.0]))]).size(),
+            10
+        );
+
+        let mut node = test_node("Sample");
+        node.set_int("amount", 0, 3);
+        node.set_int("amount", 1, 2);
+        // Two amounts give two lists.
+        let results = render_single_node(node, 0).unwrap();
+        assert_eq!(results.sublist(0), floats(vec![0.0, 50.0, 100.0]));
+        assert_eq!(results.sublist(1), floats(vec![0.0, 100.0]));
+
+        // Lists that are too large to generate fail instead of bringing down the host.
+        let render_failed = |type_name: &str, port: &str, v: f32| {
+            let mut node = test_node(type_name);
+            if port == "amount" {
+                node.set_int(port, 0, v as i32);
+            } else {
+                node.set_float(port, 0, v);
+            }
+            matches!(
+                render_single_node(node, 0),
+                Err(NetworkError::RenderFailed(1, _))
+            )
+        };
+        assert!(render_failed("Range", "end", f32::INFINITY));
+        assert!(render_failed("Range", "end", f32::NAN));
+        assert!(render_failed("Range", "step", 1e-30));
+        assert!(render_failed("Sample", "amount", i32::MAX as f32));
+        assert!(!render_failed("Sample", "amount", -5.0));
+    }
+
+    #[test]
+    fn wave() {
+        let wave = |wave_type: &str, offsets: Vec<f32>| {
+            let mut node = test_node("Wave");
+            node.set_float("period", 0, 4.0);
+            node.get_input_by_name_mut("offset").unwrap().slice = floats(offsets);
+            node.set_string("type", 0, wave_type);
+            let results = render_single_node(node, 0).unwrap();
+            (0..results.size())
+                .map(|i| results.get_float(i).round())
+                .collect::<Vec<f32>>()
+        };
+        let offsets = vec![0.0, 1.0, 2.0, 3.0];
+        assert_eq!(wave("sine", offsets.clone()), vec![50.0, 100.0, 50.0, 0.0]);
+        assert_eq!(
+            wave("square", offsets.clone()),
+            vec![0.0, 0.0, 100.0, 100.0]
+        );
+        assert_eq!(
+            wave("triangle", offsets.clone()),
+            vec![0.0, 50.0, 100.0, 50.0]
+        );
+        assert_eq!(wave("sawtooth", offsets), vec![0.0, 25.0, 50.0, 75.0]);
     }
 }
@@ -1,3 +1,102 @@
+mod element_wise;
+mod list;
+mod logic;
 mod math;
+mod random;
+mod string;
+mod subnetwork;
+mod time;
 
+pub use self::element_wise::*;
+pub use self::list::*;
+pub use self::logic::*;
 pub use self::math::*;
+pub use self::random::*;
+pub use self::string::*;
+pub use self::subnetwork::*;
+pub use self::time::*;
+
+use crate::{FunctionRepository, NetworkError, NodeId, NullFunction, PortKind, PortSlice};
+
+pub(crate) fn register_builtins(repository: &mut FunctionRepository) {
+    repository.register("Null", "core", "Does nothing.", || {
+        Box::new(NullFunction {})
+    });
+    list::register(repository);
+    logic::register(repository);
+    math::register(repository);
+    random::register(repository);
+    string::register(repository);
+    time::register(repository);
+}
+
+/// The most values a node may generate in one list. Larger lists are a render error instead
+/// of an allocation that could bring down the host.
+pub(crate) const MAX_LIST_SIZE: usize = 10_000_000;
+
+/// Check the size of a list a node is about to generate. Negative sizes give an empty list.
+pub(crate) fn check_list_size(id: NodeId, size: f64) -> Result<usize, NetworkError> {
+    if size.is_finite() && size <= MAX_LIST_SIZE as f64 {
+        Ok(size.max(0.0) as usize)
+    } else {
+        Err(NetworkError::RenderFailed(
+            id,
+            format!(
+                "Can't generate {} values; the maximum is {}.",
+                size, MAX_LIST_SIZE
+            ),
+        ))
+    }
+}
+
+/// Generators output one list per iteration. A single list is output as is; several lists
+/// are output as a nested list.
+pub(crate) fn set_output_lists(
+    ctx: &mut RenderContext,
+    id: NodeId,
+    kind: PortKind,
+    mut lists: Vec<PortSlice>,
+) {
+    if lists.len() == 1 {
+        ctx.set_output_slice(id, 0, lists.pop().unwrap());
+    } else {
+        ctx.set_output_slice(id, 0, PortSlice::new_list(kind, lists));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use crate::{
+        new_node, FunctionRepository, Network, NetworkError, Node, PortIndex, PortSlice,
+        RenderContext,
+    };
+
+    /// A node of the given type with id 1.
+    pub(crate) fn test_node(type_name: &str) -> Node {
+        new_node(&FunctionRepository::with_builtins(), 1, type_name, 0, 0).unwrap()
+    }
+
+    /// Render an output of a network that only holds the given node.
+    pub(crate) fn render_single_node(
+        node: Node,
+        output_port_index: PortIndex,
+    ) -> Result<PortSlice, NetworkError> {
+        let node_id = node.id;
+        let mut network = Network::new();
+        network.nodes.push(node);
+        network.rendered_id = node_id;
+        let mut ctx = RenderContext::new(&network);
+        network
+            .render_output(&mut ctx, node_id, output_port_index)
+            .map(|slice| (*slice).clone())
+    }
+
+    /// Render the first output of a node of the given type, with the given input values.
+    pub(crate) fn render_type(type_name: &str, inputs: Vec<(&str, PortSlice)>) -> PortSlice {
+        let mut node = test_node(type_name);
+        for (name, slice) in inputs {
+            node.get_input_by_name_mut(name).unwrap().slice = slice;
+        }
+        render_single_node(node, 0).unwrap()
+    }
+}
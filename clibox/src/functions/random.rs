@@ -0,0 +1,255 @@
+use super::{check_list_size, set_output_lists};
+use crate::random::Random;
+use crate::{
+    Function, FunctionRepository, NetworkError, Node, Point, PortKind, PortSlice, RenderContext,
+};
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    repository.register(
+        "Random Numbers",
+        "random",
+        "Generates random numbers between min and max. The same seed gives the same numbers.",
+        || Box::new(RandomNumbersFunction {}),
+    );
+    repository.register(
+        "Random Ints",
+        "random",
+        "Generates random whole numbers between min and max, both included. The same seed gives the same numbers.",
+        || Box::new(RandomIntsFunction {}),
+    );
+    repository.register(
+        "Random Items",
+        "random",
+        "Picks random items from a list; an item can be picked more than once. The same seed gives the same items.",
+        || Box::new(RandomItemsFunction {}),
+    );
+    repository.register(
+        "Random Points",
+        "random",
+        "Generates random points in a rectangle around the position. The same seed gives the same points.",
+        || Box::new(RandomPointsFunction {}),
+    );
+}
+
+pub struct RandomNumbersFunction {}
+impl Function for RandomNumbersFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_int_input_port("amount", vec![10]);
+        node.add_float_input_port("min", vec![0.0]);
+        node.add_float_input_port("max", vec![100.0]);
+        node.add_int_input_port("seed", vec![0]);
+        node.add_float_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let amount = ctx
+                .get_input_slice(node.id, 0)
+                .get_int(list_match.index(i, 0));
+            let amount = check_list_size(node.id, amount as f64)?;
+            let min = ctx
+                .get_input_slice(node.id, 1)
+                .get_float(list_match.index(i, 1));
+            let max = ctx
+                .get_input_slice(node.id, 2)
+                .get_float(list_match.index(i, 2));
+            let seed = ctx
+                .get_input_slice(node.id, 3)
+                .get_int(list_match.index(i, 3));
+            let mut random = Random::new(seed as u64);
+            lists.push(PortSlice::new_float(
+                (0..amount)
+                    .map(|_| min + random.next_f32() * (max - min))
+                    .collect(),
+            ));
+        }
+        set_output_lists(ctx, node.id, PortKind::Float, lists);
+        Ok(())
+    }
+}
+
+pub struct RandomIntsFunction {}
+impl Function for RandomIntsFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_int_input_port("amount", vec![10]);
+        node.add_int_input_port("min", vec![0]);
+        node.add_int_input_port("max", vec![100]);
+        node.add_int_input_port("seed", vec![0]);
+        node.add_int_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let amount = ctx
+                .get_input_slice(node.id, 0)
+                .get_int(list_match.index(i, 0));
+            let amount = check_list_size(node.id, amount as f64)?;
+            let min = ctx
+                .get_input_slice(node.id, 1)
+                .get_int(list_match.index(i, 1));
+            let max = ctx
+                .get_input_slice(node.id, 2)
+                .get_int(list_match.index(i, 2));
+            let seed = ctx
+                .get_input_slice(node.id, 3)
+                .get_int(list_match.index(i, 3));
+            let mut random = Random::new(seed as u64);
+            lists.push(PortSlice::new_int(
+                (0..amount).map(|_| random.next_int(min, max)).collect(),
+            ));
+        }
+        set_output_lists(ctx, node.id, PortKind::Int, lists);
+        Ok(())
+    }
+}
+
+pub struct RandomItemsFunction {}
+impl Function for RandomItemsFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_generic_list_input_port("list");
+        node.add_int_input_port("amount", vec![5]);
+        node.add_int_input_port("seed", vec![0]);
+        node.add_generic_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_list = ctx.get_input_slice(node.id, 0);
+        let amount = check_list_size(node.id, ctx.get_input_slice(node.id, 1).get_int(0) as f64)?;
+        let mut random = Random::new(ctx.get_input_slice(node.id, 2).get_int(0) as u64);
+        let picked = if in_list.size() == 0 {
+            PortSlice::new_empty(in_list.kind())
+        } else {
+            let indices: Vec<usize> = (0..amount)
+                .map(|_| random.next_index(in_list.size()))
+                .collect();
+            in_list.select(&indices)
+        };
+        ctx.set_output_slice(node.id, 0, picked);
+        Ok(())
+    }
+}
+
+pub struct RandomPointsFunction {}
+impl Function for RandomPointsFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_int_input_port("amount", vec![10]);
+        node.add_point_input_port("position", vec![Point::new(0.0, 0.0)]);
+        node.add_float_input_port("width", vec![100.0]);
+        node.add_float_input_port("height", vec![100.0]);
+        node.add_int_input_port("seed", vec![0]);
+        node.add_point_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let amount = ctx
+                .get_input_slice(node.id, 0)
+                .get_int(list_match.index(i, 0));
+            let amount = check_list_size(node.id, amount as f64)?;
+            let position = ctx
+                .get_input_slice(node.id, 1)
+                .get_point(list_match.index(i, 1));
+            let width = ctx
+                .get_input_slice(node.id, 2)
+                .get_float(list_match.index(i, 2));
+            let height = ctx
+                .get_input_slice(node.id, 3)
+                .get_float(list_match.index(i, 3));
+            let seed = ctx
+                .get_input_slice(node.id, 4)
+                .get_int(list_match.index(i, 4));
+            let mut random = Random::new(seed as u64);
+            let left = position.x - width / 2.0;
+            let top = position.y - height / 2.0;
+            lists.push(PortSlice::new_point(
+                (0..amount)
+                    .map(|_| {
+                        let x = left + random.next_f32() * width;
+                        let y = top + random.next_f32() * height;
+                        Point::new(x, y)
+                    })
+                    .collect(),
+            ));
+        }
+        set_output_lists(ctx, node.id, PortKind::Point, lists);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_util::{render_single_node, test_node};
+    use super::*;
+
+    #[test]
+    fn random_nodes() {
+        let render_seed = |type_name: &str, seed: i32| {
+            let mut node = test_node(type_name);
+            node.set_int("seed", 0, seed);
+            render_single_node(node, 0).unwrap()
+        };
+        for type_name in &["Random Numbers", "Random Ints", "Random Points"] {
+            let results = render_seed(type_name, 42);
+            assert_eq!(results.size(), 10);
+            assert_eq!(results, render_seed(type_name, 42));
+            assert_ne!(results, render_seed(type_name, 43));
+        }
+
+        let numbers = render_seed("Random Numbers", 1);
+        assert!((0..10).all(|i| (0.0..100.0).contains(&numbers.get_float(i))));
+        // The output may never change between versions or platforms.
+        assert_eq!(
+            render_seed("Random Ints", 7),
+            PortSlice::new_int(vec![27, 59, 78, 16, 25, 64, 30, 46, 61, 50])
+        );
+
+        let mut node = test_node("Random Ints");
+        node.set_int("min", 0, 3);
+        node.set_int("max", 0, -3);
+        node.set_int("amount", 0, 100);
+        let ints = render_single_node(node, 0).unwrap();
+        assert!((0..100).all(|i| (-3..=3).contains(&ints.get_int(i))));
+
+        let mut node = test_node("Random Points");
+        node.set_point("position", 0, Point::new(100.0, 50.0));
+        node.set_float("width", 0, 10.0);
+        node.set_float("height", 0, 20.0);
+        let points = render_single_node(node, 0).unwrap();
+        assert!((0..10).all(|i| {
+            let p = points.get_point(i);
+            (95.0..105.0).contains(&p.x) && (40.0..60.0).contains(&p.y)
+        }));
+
+        let mut node = test_node("Random Items");
+        node.inputs[0].slice = PortSlice::new_string(vec!["a", "b"]);
+        node.set_int("amount", 0, 20);
+        let items = render_single_node(node, 0).unwrap();
+        assert_eq!(items.kind(), PortKind::String);
+        assert_eq!(items.size(), 20);
+        assert!((0..20).all(|i| ["a", "b"].contains(&items.get_string(i).as_str())));
+
+        // Amounts that are too large to generate fail instead of bringing down the host.
+        for type_name in &[
+            "Random Numbers",
+            "Random Ints",
+            "Random Points",
+            "Random Items",
+        ] {
+            let mut node = test_node(type_name);
+            if let Some(list) = node.get_input_by_name_mut("list") {
+                list.slice = PortSlice::new_int(vec![1]);
+            }
+            node.set_int("amount", 0, i32::MAX);
+            assert!(matches!(
+                render_single_node(node, 0),
+                Err(NetworkError::RenderFailed(1, _))
+            ));
+        }
+    }
+}
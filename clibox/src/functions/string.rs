@@ -0,0 +1,441 @@
+use super::{register_element_wise, set_output_lists, ElementOp, ElementWiseFunction};
+use crate::{Function, FunctionRepository, NetworkError, Node, PortKind, PortSlice, RenderContext};
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    register_string(
+        repository,
+        "Concatenate",
+        "Joins strings together.",
+        &[("string1", ""), ("string2", ""), ("string3", "")],
+        |v| Ok(v.concat()),
+    );
+    register_string(
+        repository,
+        "Format",
+        "Fills in the placeholders of a template. \"{}\" takes the next value, \"{0}\" the first.",
+        &[("template", "{} {}"), ("v1", ""), ("v2", ""), ("v3", "")],
+        |v| Ok(format_template(&v[0], &v[1..])),
+    );
+    register_string(
+        repository,
+        "Upper Case",
+        "Converts a string to upper case.",
+        S,
+        |v| Ok(v[0].to_uppercase()),
+    );
+    register_string(
+        repository,
+        "Lower Case",
+        "Converts a string to lower case.",
+        S,
+        |v| Ok(v[0].to_lowercase()),
+    );
+    register_string(
+        repository,
+        "Trim",
+        "Removes whitespace from the start and end of a string.",
+        S,
+        |v| Ok(v[0].trim().to_owned()),
+    );
+    register_string(
+        repository,
+        "Replace",
+        "Replaces all occurrences of a string with another.",
+        &[("s", ""), ("old", ""), ("new", "")],
+        |v| {
+            if v[1].is_empty() {
+                Ok(v[0].clone())
+            } else {
+                Ok(v[0].replace(&v[1], &v[2]))
+            }
+        },
+    );
+    repository.register(
+        "Split",
+        "string",
+        "Splits a string into a list at every separator. An empty separator splits it into characters.",
+        || Box::new(SplitFunction {}),
+    );
+    repository.register(
+        "Characters",
+        "string",
+        "Splits a string into a list of characters.",
+        || Box::new(CharactersFunction {}),
+    );
+    repository.register(
+        "Substring",
+        "string",
+        "The characters of a string from start up to (but not including) end.",
+        || Box::new(SubstringFunction {}),
+    );
+    repository.register(
+        "Length",
+        "string",
+        "The number of characters in a string.",
+        || Box::new(LengthFunction {}),
+    );
+    repository.register(
+        "Number To String",
+        "string",
+        "Formats a number with the given amount of decimals, from 0 to 20.",
+        || Box::new(NumberToStringFunction {}),
+    );
+    repository.register(
+        "Parse Floats",
+        "string",
+        "Parses a list of numbers separated by the separator.",
+        || Box::new(ParseFloatsFunction {}),
+    );
+}
+
+pub type StringOp = ElementOp<String>;
+pub type StringFunction = ElementWiseFunction<String>;
+
+const S: &[(&str, &str)] = &[("s", "")];
+
+fn register_string(
+    repository: &mut FunctionRepository,
+    type_name: &str,
+    description: &str,
+    ports: &'static [(&'static str, &'static str)],
+    op: StringOp,
+) {
+    register_element_wise(repository, type_name, "string", description, ports, op);
+}
+
+/// Replace "{}" with the next value and "{n}" with the value at index n.
+/// Placeholders without a value are left as they are.
+fn format_template(template: &str, values: &[String]) -> String {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let close = match after.find('}') {
+            Some(close) => close,
+            None => break,
+        };
+        out.push_str(&rest[..open]);
+        let placeholder = &after[..close];
+        let index = if placeholder.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            placeholder.parse::<usize>().ok()
+        };
+        match index.and_then(|i| values.get(i)) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub struct SplitFunction {}
+impl Function for SplitFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_string_input_port("s", vec![""]);
+        node.add_string_input_port("separator", vec![";"]);
+        node.add_string_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut lists = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let s = ctx
+                .get_input_slice(node.id, 0)
+                .get_string(list_match.index(i, 0));
+            let separator = ctx
+                .get_input_slice(node.id, 1)
+                .get_string(list_match.index(i, 1));
+            let parts = if separator.is_empty() {
+                s.chars().map(String::from).collect()
+            } else {
+                s.split(separator.as_str()).map(String::from).collect()
+            };
+            lists.push(PortSlice::String(parts));
+        }
+        set_output_lists(ctx, node.id, PortKind::String, lists);
+        Ok(())
+    }
+}
+
+pub struct CharactersFunction {}
+impl Function for CharactersFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_string_input_port("s", vec![""]);
+        node.add_string_output_port("list");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_s = ctx.get_input_slice(node.id, 0);
+        let lists = (0..in_s.size())
+            .map(|i| PortSlice::String(in_s.get_string(i).chars().map(String::from).collect()))
+            .collect();
+        set_output_lists(ctx, node.id, PortKind::String, lists);
+        Ok(())
+    }
+}
+
+pub struct SubstringFunction {}
+impl Function for SubstringFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_string_input_port("s", vec![""]);
+        node.add_int_input_port("start", vec![0]);
+        node.add_int_input_port("end", vec![4]);
+        node.add_string_output_port("out");
+    }
+
+    /// Positions count characters, not bytes. They are clamped to the string.
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let s = ctx
+                .get_input_slice(node.id, 0)
+                .get_string(list_match.index(i, 0));
+            let start = ctx
+                .get_input_slice(node.id, 1)
+                .get_int(list_match.index(i, 1));
+            let end = ctx
+                .get_input_slice(node.id, 2)
+                .get_int(list_match.index(i, 2));
+            let start = start.max(0) as usize;
+            let end = (end.max(0) as usize).max(start);
+            results.push(s.chars().skip(start).take(end - start).collect());
+        }
+        ctx.set_output_slice(node.id, 0, PortSlice::String(results));
+        Ok(())
+    }
+}
+
+pub struct LengthFunction {}
+impl Function for LengthFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_string_input_port("s", vec![""]);
+        node.add_int_output_port("length");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_s = ctx.get_input_slice(node.id, 0);
+        let lengths = (0..in_s.size())
+            .map(|i| in_s.get_string(i).chars().count() as i32)
+            .collect();
+        ctx.set_output_slice(node.id, 0, PortSlice::new_int(lengths));
+        Ok(())
+    }
+}
+
+/// The most decimals Number To String writes. More would only add noise to an f32.
+const MAX_DECIMALS: i32 = 20;
+
+pub struct NumberToStringFunction {}
+impl Function for NumberToStringFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_float_input_port("v", vec![0.0]);
+        node.add_int_input_port("decimals", vec![2]);
+        node.add_string_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let list_match = ctx.list_match(node);
+        let mut results = Vec::with_capacity(list_match.len());
+        for i in 0..list_match.len() {
+            let v = ctx
+                .get_input_slice(node.id, 0)
+                .get_float(list_match.index(i, 0));
+            let decimals = ctx
+                .get_input_slice(node.id, 1)
+                .get_int(list_match.index(i, 1));
+            let decimals = decimals.clamp(0, MAX_DECIMALS) as usize;
+            results.push(format!("{:.*}", decimals, v));
+        }
+        ctx.set_output_slice(node.id, 0, PortSlice::String(results));
+        Ok(())
+    }
+}
+
+pub struct ParseFloatsFunction {}
+impl Function for ParseFloatsFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_string_input_port("s", vec!["1;2;3;4;5"]);
+        node.add_string_input_port("separator", vec![";"]);
+        node.add_float_output_port("out");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let in_s = ctx.get_input_slice(node.id, 0);
+        let separator = ctx.get_input_slice(node.id, 1).get_string(0);
+        let mut results = Vec::new();
+        // The numbers of all input strings are combined into a single list.
+        for i in 0..in_s.size() {
+            let s = in_s.get_string(i);
+            let parts: Vec<&str> = if separator.is_empty() {
+                vec![&s]
+            } else {
+                s.split(separator.as_str()).collect()
+            };
+            for part in parts {
+                match part.trim().parse::<f32>() {
+                    Ok(v) => results.push(v),
+                    Err(_) => {
+                        return Err(NetworkError::RenderFailed(
+                            node.id,
+                            format!("Could not parse \"{}\" as a number.", part),
+                        ));
+                    }
+                }
+            }
+        }
+        ctx.set_output_floats(node.id, 0, results);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_util::{render_single_node, render_type, test_node};
+    use super::*;
+
+    fn strings(values: Vec<&str>) -> PortSlice {
+        PortSlice::new_string(values)
+    }
+
+    #[test]
+    fn element_wise() {
+        assert_eq!(
+            render_type(
+                "Concatenate",
+                vec![
+                    ("string1", strings(vec!["a", "b"])),
+                    ("string2", strings(vec!["-"])),
+                    ("string3", strings(vec!["z"]))
+                ]
+            ),
+            strings(vec!["a-z", "b-z"])
+        );
+        assert_eq!(
+            render_type(
+                "Format",
+                vec![
+                    ("template", strings(vec!["{} + {} = {2} {x} {"])),
+                    ("v1", strings(vec!["1"])),
+                    ("v2", strings(vec!["2"])),
+                    ("v3", strings(vec!["3"]))
+                ]
+            ),
+            strings(vec!["1 + 2 = 3 {x} {"])
+        );
+        let s = |type_name: &str, s: &str| render_type(type_name, vec![("s", strings(vec![s]))]);
+        assert_eq!(s("Upper Case", "abc"), strings(vec!["ABC"]));
+        assert_eq!(s("Lower Case", "ABC"), strings(vec!["abc"]));
+        assert_eq!(s("Trim", " a b "), strings(vec!["a b"]));
+        assert_eq!(
+            render_type(
+                "Replace",
+                vec![
+                    ("s", strings(vec!["a-b-c"])),
+                    ("old", strings(vec!["-"])),
+                    ("new", strings(vec!["+"]))
+                ]
+            ),
+            strings(vec!["a+b+c"])
+        );
+    }
+
+    #[test]
+    fn split_and_characters() {
+        assert_eq!(
+            render_type(
+                "Split",
+                vec![
+                    ("s", strings(vec!["a,b,c"])),
+                    ("separator", strings(vec![","]))
+                ]
+            ),
+            strings(vec!["a", "b", "c"])
+        );
+        let split = render_type("Split", vec![("s", strings(vec!["a;b", "c"]))]);
+        assert_eq!(split.sublist(0), strings(vec!["a", "b"]));
+        assert_eq!(split.sublist(1), strings(vec!["c"]));
+        assert_eq!(
+            render_type("Characters", vec![("s", strings(vec!["héllo"]))]),
+            strings(vec!["h", "é", "l", "l", "o"])
+        );
+        assert_eq!(
+            render_type("Length", vec![("s", strings(vec!["héllo", ""]))]),
+            PortSlice::new_int(vec![5, 0])
+        );
+
+        let mut node = test_node("Substring");
+        node.set_string("s", 0, "héllo world");
+        node.set_int("start", 0, 1);
+        node.set_int("end", 0, 30);
+        assert_eq!(
+            render_single_node(node, 0).unwrap(),
+            strings(vec!["éllo world"])
+        );
+    }
+
+    #[test]
+    fn number_to_string() {
+        let mut node = test_node("Number To String");
+        node.set_float("v", 0, 1.23456);
+        node.set_float("v", 1, 2.0);
+        node.set_int("decimals", 0, 3);
+        assert_eq!(
+            render_single_node(node, 0).unwrap(),
+            strings(vec!["1.235", "2.000"])
+        );
+
+        // The amount of decimals is clamped between 0 and 20.
+        let mut node = test_node("Number To String");
+        node.set_float("v", 0, 1.5);
+        node.set_int("decimals", 0, -3);
+        node.set_int("decimals", 1, i32::MAX);
+        assert_eq!(
+            render_single_node(node, 0).unwrap(),
+            strings(vec!["2", "1.50000000000000000000"])
+        );
+    }
+
+    #[test]
+    fn parse_floats() {
+        let results = render_type("Parse Floats", vec![]);
+        assert_eq!(results, PortSlice::new_float(vec![1.0, 2.0, 3.0, 4.0, 5.0]));
+        assert_eq!(results.get_float(5), 1.0);
+    }
+
+    #[test]
+    fn parse_floats_separator() {
+        let mut node = test_node("Parse Floats");
+        node.set_string("s", 0, "1, 2.5, 3");
+        node.set_string("separator", 0, ",");
+        let results = render_single_node(node, 0).unwrap();
+        assert_eq!(results, PortSlice::new_float(vec![1.0, 2.5, 3.0]));
+    }
+
+    #[test]
+    fn parse_floats_invalid() {
+        let mut node = test_node("Parse Floats");
+        node.set_string("s", 0, "1;x;3");
+        let err = render_single_node(node, 0).unwrap_err();
+        assert_eq!(
+            err,
+            NetworkError::RenderFailed(1, "Could not parse \"x\" as a number.".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_floats_multiple_strings() {
+        let mut node = test_node("Parse Floats");
+        node.set_string("s", 0, "1;2");
+        node.set_string("s", 1, "3");
+        let results = render_single_node(node, 0).unwrap();
+        assert_eq!(results.size(), 3);
+        assert_eq!(results.get_float(2), 3.0);
+    }
+}
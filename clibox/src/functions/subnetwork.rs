@@ -0,0 +1,106 @@
+use crate::{
+    Function, Network, NetworkError, Node, NodeId, Port, PortDirection, PortIndex, PublishedPort,
+    RenderContext,
+};
+
+/// Renders an inner network as if it were a single node.
+///
+/// The published inputs of the inner network become the node's inputs, and the outputs of the
+/// inner rendered node become the node's outputs.
+pub struct SubnetworkFunction {
+    pub network: Network,
+}
+
+impl SubnetworkFunction {
+    /// The first published input whose port is not in the inner network anymore.
+    fn missing_input(&self) -> Option<&PublishedPort> {
+        self.network
+            .published_inputs
+            .iter()
+            .find(|p| self.network.get_input_port(p.node_id, p.port).is_none())
+    }
+}
+
+impl Function for SubnetworkFunction {
+    /// Published inputs whose port is missing get no input; rendering the node then fails.
+    fn setup(&self, node: &mut Node) {
+        for published in &self.network.published_inputs {
+            let inner = match self
+                .network
+                .get_input_port(published.node_id, published.port)
+            {
+                Some(inner) => inner,
+                None => continue,
+            };
+            node.inputs.push(Port {
+                name: published.name.clone(),
+                kind: inner.kind,
+                slice: inner.slice.clone(),
+                direction: PortDirection::In,
+                range: inner.range,
+                generic: inner.generic,
+            });
+        }
+        if let Some(rendered) = self.network.get_rendered_node() {
+            for output in &rendered.outputs {
+                let mut port = Port::new_output(&output.name, output.kind);
+                port.generic = output.generic;
+                node.outputs.push(port);
+            }
+        }
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        if let Some(published) = self.missing_input() {
+            let error = NetworkError::InputPortNotFound(published.node_id, published.port);
+            return Err(NetworkError::RenderFailed(node.id, error.to_string()));
+        }
+        let mut child = RenderContext::new(&self.network);
+        child.frame = ctx.frame;
+        child.fps = ctx.fps;
+        for (i, published) in self.network.published_inputs.iter().enumerate() {
+            let slice = ctx.get_input_shared(node.id, i);
+            child.set_input_value(published.node_id, published.port, slice);
+        }
+        let rendered_id = match self.network.get_rendered_node() {
+            Some(rendered) => rendered.id,
+            None => {
+                let error = NetworkError::NoRenderedNode;
+                return Err(NetworkError::RenderFailed(node.id, error.to_string()));
+            }
+        };
+        let targets: Vec<(NodeId, PortIndex)> = (0..node.outputs.len())
+            .map(|port_index| (rendered_id, port_index))
+            .collect();
+        let slices = self
+            .network
+            .render_outputs(&mut child, &targets)
+            .map_err(|error| NetworkError::RenderFailed(node.id, error.to_string()))?;
+        for (port_index, slice) in slices.into_iter().enumerate() {
+            ctx.set_output_shared(node.id, port_index, slice);
+        }
+        Ok(())
+    }
+
+    fn is_time_dependent(&self) -> bool {
+        self.network.nodes.iter().any(|n| n.is_time_dependent())
+    }
+
+    fn subnetwork(&self) -> Option<&Network> {
+        Some(&self.network)
+    }
+}
+
+/// The type name of subnetwork nodes. Subnetworks are not created through the repository.
+pub const SUBNETWORK_TYPE_NAME: &str = "Subnetwork";
+
+/// Create a node that renders the given network. Its ports are set up from the published
+/// inputs and the rendered node of the network.
+pub fn new_subnetwork_node(id: NodeId, name: &str, network: Network, x: i32, y: i32) -> Node {
+    let function = SubnetworkFunction { network };
+    let mut node = Node::new(id, name, x, y);
+    node.type_name = SUBNETWORK_TYPE_NAME.to_owned();
+    function.setup(&mut node);
+    node.function = Box::new(function);
+    node
+}
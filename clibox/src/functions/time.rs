@@ -0,0 +1,50 @@
+use crate::{Function, FunctionRepository, NetworkError, Node, PortSlice, RenderContext};
+
+pub(crate) fn register(repository: &mut FunctionRepository) {
+    repository.register(
+        "Frame",
+        "time",
+        "The number of the frame being rendered.",
+        || Box::new(FrameFunction {}),
+    );
+    repository.register(
+        "Time",
+        "time",
+        "The time of the frame being rendered, in seconds.",
+        || Box::new(TimeFunction {}),
+    );
+}
+
+pub struct FrameFunction {}
+impl Function for FrameFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_int_output_port("frame");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let frame = ctx.frame;
+        ctx.set_output_slice(node.id, 0, PortSlice::new_int(vec![frame]));
+        Ok(())
+    }
+
+    fn is_time_dependent(&self) -> bool {
+        true
+    }
+}
+
+pub struct TimeFunction {}
+impl Function for TimeFunction {
+    fn setup(&self, node: &mut Node) {
+        node.add_float_output_port("time");
+    }
+
+    fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        let time = ctx.time();
+        ctx.set_output_floats(node.id, 0, vec![time]);
+        Ok(())
+    }
+
+    fn is_time_dependent(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Point {
+        Point { x, y }
+    }
+
+    /// Parse a point written as "x,y".
+    pub fn parse(s: &str) -> Option<Point> {
+        let mut parts = s.split(',');
+        let x = parts.next()?.trim().parse::<f32>().ok()?;
+        let y = parts.next()?.trim().parse::<f32>().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Point { x, y })
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+/// An RGBA color. All components are between 0 and 1.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn gray(v: f32) -> Color {
+        Color::new(v, v, v, 1.0)
+    }
+
+    /// Parse a color written as "#rrggbb" or "#rrggbbaa". The "#" is optional.
+    pub fn from_hex(s: &str) -> Option<Color> {
+        let s = s.trim();
+        let s = s.strip_prefix('#').unwrap_or(s);
+        if (s.len() != 6 && s.len() != 8) || !s.is_ascii() {
+            return None;
+        }
+        let component = |i: usize| -> Option<f32> {
+            let v = u8::from_str_radix(&s[i..i + 2], 16).ok()?;
+            Some(f32::from(v) / 255.0)
+        };
+        let a = if s.len() == 8 { component(6)? } else { 1.0 };
+        Some(Color::new(component(0)?, component(2)?, component(4)?, a))
+    }
+
+    /// Format the color as "#rrggbb", or "#rrggbbaa" if it is not fully opaque.
+    pub fn to_hex(&self) -> String {
+        let component = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let rgb = format!(
+            "#{:02x}{:02x}{:02x}",
+            component(self.r),
+            component(self.g),
+            component(self.b)
+        );
+        if self.a >= 1.0 {
+            rgb
+        } else {
+            format!("{}{:02x}", rgb, component(self.a))
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Color {
+        Color::gray(0.0)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    pub points: Vec<Point>,
+    pub closed: bool,
+    pub fill: Option<Color>,
+}
+
+impl Path {
+    pub fn new(points: Vec<Point>, closed: bool) -> Path {
+        Path {
+            points,
+            closed,
+            fill: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Geometry {
+    pub paths: Vec<Path>,
+}
+
+impl Geometry {
+    pub fn new(paths: Vec<Path>) -> Geometry {
+        Geometry { paths }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &Point> {
+        self.paths.iter().flat_map(|p| p.points.iter())
+    }
+}
+
+/// Geometry is written as SVG path data, e.g. "M0,0 L10,0 L10,10 Z".
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        for path in &self.paths {
+            for (i, point) in path.points.iter().enumerate() {
+                if !first {
+                    write!(f, " ")?;
+                }
+                first = false;
+                let command = if i == 0 { "M" } else { "L" };
+                write!(f, "{}{}", command, point)?;
+            }
+            if path.closed && !path.points.is_empty() {
+                write!(f, " Z")?;
+            }
+        }
+        Ok(())
+    }
+}
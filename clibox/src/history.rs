@@ -0,0 +1,509 @@
+//! Undo and redo for network edits.
+//!
+//! Every change to a network is an `Edit`. Applying an edit returns the edit that reverses it,
+//! which is what the `History` keeps on its undo and redo stacks.
+
+use crate::{
+    Connection, ListMatching, Network, NetworkError, Node, NodeId, PortIndex, PortSlice,
+    PublishedPort,
+};
+
+pub enum Edit {
+    AddNode(Node),
+    /// Delete the node together with its connections.
+    DeleteNode(NodeId),
+    /// Put back a deleted node. This is the reverse of `DeleteNode`.
+    RestoreNode(Box<DeletedNode>),
+    Connect(Connection),
+    /// Remove the connection to the given input. Fails if the input is not connected.
+    Disconnect(NodeId, PortIndex),
+    /// Replace the values of an input port. Values of another kind are converted to the kind of
+    /// the port; values that can't be converted are an error.
+    SetValue(NodeId, PortIndex, PortSlice),
+    SetListMatching(NodeId, ListMatching),
+    Move(NodeId, i32, i32),
+    Rename(NodeId, String),
+    SetRenderedNode(NodeId),
+    /// Several edits that are done and undone as one step.
+    Group(Vec<Edit>),
+}
+
+/// Everything needed to put a deleted node back where it was.
+pub struct DeletedNode {
+    index: usize,
+    node: Node,
+    connections: Vec<Connection>,
+    published_inputs: Vec<(usize, PublishedPort)>,
+    rendered: bool,
+}
+
+impl Edit {
+    /// Apply the edit to the network and return the edit that undoes it.
+    /// If the edit fails the network is left unchanged.
+    pub fn apply(self, network: &mut Network) -> Result<Edit, NetworkError> {
+        match self {
+            Edit::AddNode(node) => {
+                let id = node.id;
+                if network.get_node(id).is_some() {
+                    return Err(NetworkError::DuplicateId(id));
+                }
+                network.nodes.push(node);
+                Ok(Edit::DeleteNode(id))
+            }
+            Edit::DeleteNode(id) => {
+                let index = network
+                    .nodes
+                    .iter()
+                    .position(|n| n.id == id)
+                    .ok_or(NetworkError::NodeNotFound(id))?;
+                let connections = network
+                    .connections
+                    .iter()
+                    .filter(|c| c.output_id == id || c.input_id == id)
+                    .cloned()
+                    .collect();
+                let published_inputs = network
+                    .published_inputs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.node_id == id)
+                    .map(|(i, p)| (i, p.clone()))
+                    .collect();
+                let rendered = network.rendered_id == id;
+                let node = network.delete_node(id).unwrap();
+                Ok(Edit::RestoreNode(Box::new(DeletedNode {
+                    index,
+                    node,
+                    connections,
+                    published_inputs,
+                    rendered,
+                })))
+            }
+            Edit::RestoreNode(deleted) => {
+                let deleted = *deleted;
+                let id = deleted.node.id;
+                if network.get_node(id).is_some() {
+                    return Err(NetworkError::DuplicateId(id));
+                }
+                let index = deleted.index.min(network.nodes.len());
+                // Connecting replaces existing connections, so keep them to roll back to.
+                let previous_connections = network.connections.clone();
+                network.nodes.insert(index, deleted.node);
+                for conn in deleted.connections {
+                    let result = network.connect(
+                        conn.output_id,
+                        conn.output_port,
+                        conn.input_id,
+                        conn.input_port,
+                    );
+                    if let Err(error) = result {
+                        network.nodes.remove(index);
+                        network.connections = previous_connections;
+                        return Err(error);
+                    }
+                }
+                for (i, published) in deleted.published_inputs {
+                    let i = i.min(network.published_inputs.len());
+                    network.published_inputs.insert(i, published);
+                }
+                if deleted.rendered {
+                    network.rendered_id = id;
+                }
+                Ok(Edit::DeleteNode(id))
+            }
+            Edit::Connect(conn) => {
+                let previous = network
+                    .get_connection_with_input(conn.input_id, conn.input_port)
+                    .cloned();
+                network.connect(
+                    conn.output_id,
+                    conn.output_port,
+                    conn.input_id,
+                    conn.input_port,
+                )?;
+                Ok(match previous {
+                    Some(previous) => Edit::Connect(previous),
+                    None => Edit::Disconnect(conn.input_id, conn.input_port),
+                })
+            }
+            Edit::Disconnect(input_id, input_port) => {
+                match network.disconnect(input_id, input_port) {
+                    Some(conn) => Ok(Edit::Connect(conn)),
+                    None => Err(NetworkError::NotConnected(input_id, input_port)),
+                }
+            }
+            Edit::SetValue(id, port_index, slice) => {
+                let node = network
+                    .get_node_mut(id)
+                    .ok_or(NetworkError::NodeNotFound(id))?;
+                let port = node
+                    .inputs
+                    .get_mut(port_index)
+                    .ok_or(NetworkError::InputPortNotFound(id, port_index))?;
+                let slice = if port.generic || slice.kind() == port.kind {
+                    slice
+                } else if slice.kind().can_convert_to(port.kind) {
+                    slice
+                        .try_convert(port.kind)
+                        .map_err(|value| NetworkError::InvalidValue(id, port_index, value))?
+                } else {
+                    return Err(NetworkError::IncompatibleKinds(slice.kind(), port.kind));
+                };
+                let previous = std::mem::replace(&mut port.slice, slice);
+                node.mark_dirty();
+                Ok(Edit::SetValue(id, port_index, previous))
+            }
+            Edit::SetListMatching(id, list_matching) => {
+                let node = network
+                    .get_node_mut(id)
+                    .ok_or(NetworkError::NodeNotFound(id))?;
+                let previous = node.list_matching;
+                node.set_list_matching(list_matching);
+                Ok(Edit::SetListMatching(id, previous))
+            }
+            Edit::Move(id, x, y) => {
+                let node = network.get_node(id).ok_or(NetworkError::NodeNotFound(id))?;
+                let (previous_x, previous_y) = (node.x, node.y);
+                network.set_position(id, x, y)?;
+                Ok(Edit::Move(id, previous_x, previous_y))
+            }
+            Edit::Rename(id, name) => {
+                let node = network.get_node(id).ok_or(NetworkError::NodeNotFound(id))?;
+                let previous = node.name.clone();
+                network.rename_node(id, &name)?;
+                Ok(Edit::Rename(id, previous))
+            }
+            Edit::SetRenderedNode(id) => {
+                let previous = network.rendered_id;
+                // Id 0 means no node is rendered.
+                if id == 0 {
+                    network.rendered_id = 0;
+                } else {
+                    network.set_rendered_node(id)?;
+                }
+                Ok(Edit::SetRenderedNode(previous))
+            }
+            Edit::Group(edits) => {
+                let mut inverses = Vec::with_capacity(edits.len());
+                for edit in edits {
+                    match edit.apply(network) {
+                        Ok(inverse) => inverses.push(inverse),
+                        Err(error) => {
+                            // Roll back the edits that were already applied. If that fails,
+                            // report the first failure but still undo as much as possible.
+                            let mut rollback_error = None;
+                            for inverse in inverses.into_iter().rev() {
+                                if let Err(err) = inverse.apply(network) {
+                                    rollback_error.get_or_insert(err);
+                                }
+                            }
+                            return Err(match rollback_error {
+                                Some(rollback_error) => NetworkError::RollbackFailed(
+                                    Box::new(error),
+                                    Box::new(rollback_error),
+                                ),
+                                None => error,
+                            });
+                        }
+                    }
+                }
+                inverses.reverse();
+                Ok(Edit::Group(inverses))
+            }
+        }
+    }
+}
+
+/// The undo and redo stacks of a network.
+///
+/// Edits are made through `apply` so they can be undone. Edits applied between `begin_group`
+/// and `end_group` are undone in one step; groups can be nested.
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>,
+    group: Vec<Edit>,
+    group_depth: usize,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    /// Apply the edit and remember how to undo it. This clears the redo stack.
+    pub fn apply(&mut self, network: &mut Network, edit: Edit) -> Result<(), NetworkError> {
+        let inverse = edit.apply(network)?;
+        if self.group_depth > 0 {
+            self.group.push(inverse);
+        } else {
+            self.undo_stack.push(inverse);
+        }
+        self.redo_stack.clear();
+        Ok(())
+    }
+
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth == 0 && !self.group.is_empty() {
+            let mut inverses = std::mem::take(&mut self.group);
+            inverses.reverse();
+            self.undo_stack.push(Edit::Group(inverses));
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty() || !self.group.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Undo the last step. Returns false if there was nothing to undo.
+    /// A group that is still open is closed first.
+    ///
+    /// Undo can fail if the network was changed without going through the history.
+    /// The step is then dropped.
+    pub fn undo(&mut self, network: &mut Network) -> Result<bool, NetworkError> {
+        self.close_groups();
+        match self.undo_stack.pop() {
+            Some(edit) => {
+                self.redo_stack.push(edit.apply(network)?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Redo the last undone step. Returns false if there was nothing to redo.
+    pub fn redo(&mut self, network: &mut Network) -> Result<bool, NetworkError> {
+        match self.redo_stack.pop() {
+            Some(edit) => {
+                self.undo_stack.push(edit.apply(network)?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group.clear();
+        self.group_depth = 0;
+    }
+
+    fn close_groups(&mut self) {
+        while self.group_depth > 0 {
+            self.end_group();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{new_node, FunctionRepository, PortKind, RenderContext};
+
+    fn render(network: &Network) -> f32 {
+        let mut ctx = RenderContext::new(network);
+        network.render(&mut ctx).unwrap();
+        ctx.get_output_slice(network.rendered_id, 0)
+            .unwrap()
+            .get_float(0)
+    }
+
+    #[test]
+    fn undo_redo() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut history = History::new();
+        let value = new_node(&repo, 1, "Value", 0, 0).unwrap();
+        let add = new_node(&repo, 2, "Add", 0, 1).unwrap();
+        history.apply(&mut network, Edit::AddNode(value)).unwrap();
+        history.apply(&mut network, Edit::AddNode(add)).unwrap();
+        history
+            .apply(&mut network, Edit::Connect(Connection::new(1, 0, 2, 0)))
+            .unwrap();
+        history
+            .apply(&mut network, Edit::SetRenderedNode(2))
+            .unwrap();
+        history
+            .apply(
+                &mut network,
+                Edit::SetValue(1, 0, PortSlice::new_float(vec![3.0])),
+            )
+            .unwrap();
+        history.apply(&mut network, Edit::Move(2, 5, 6)).unwrap();
+        assert_eq!(render(&network), 3.0);
+
+        history.undo(&mut network).unwrap();
+        assert_eq!(network.get_node(2).unwrap().x, 0);
+        history.undo(&mut network).unwrap();
+        assert_eq!(render(&network), 0.0);
+        history.redo(&mut network).unwrap();
+        assert_eq!(render(&network), 3.0);
+
+        // Deleting the node and undoing puts back its connections.
+        history.apply(&mut network, Edit::DeleteNode(1)).unwrap();
+        assert!(network.connections.is_empty());
+        assert!(!history.can_redo());
+        history.undo(&mut network).unwrap();
+        assert_eq!(network.connections, vec![Connection::new(1, 0, 2, 0)]);
+        assert_eq!(render(&network), 3.0);
+
+        while history.undo(&mut network).unwrap() {}
+        assert!(network.nodes.is_empty());
+        assert_eq!(network.rendered_id, 0);
+        // Redoing everything deletes the node again.
+        while history.redo(&mut network).unwrap() {}
+        assert_eq!(network.nodes.len(), 1);
+        assert_eq!(render(&network), 0.0);
+    }
+
+    #[test]
+    fn groups() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Value", 1, 0).unwrap());
+        network.rename_node(1, "value1").unwrap();
+        network.rename_node(2, "value2").unwrap();
+        let mut history = History::new();
+        history.begin_group();
+        history
+            .apply(&mut network, Edit::Rename(1, "a".to_owned()))
+            .unwrap();
+        history.begin_group();
+        history
+            .apply(&mut network, Edit::Rename(2, "b".to_owned()))
+            .unwrap();
+        history.end_group();
+        history.end_group();
+        assert_eq!(history.undo_stack.len(), 1);
+        history.undo(&mut network).unwrap();
+        assert_eq!(network.get_node(1).unwrap().name, "value1");
+        assert_eq!(network.get_node(2).unwrap().name, "value2");
+        assert!(!history.can_undo());
+
+        // A group that fails halfway is rolled back.
+        let edit = Edit::Group(vec![
+            Edit::Rename(1, "a".to_owned()),
+            Edit::Rename(2, "a".to_owned()),
+        ]);
+        assert_eq!(
+            history.apply(&mut network, edit).err(),
+            Some(NetworkError::DuplicateName("a".to_owned()))
+        );
+        assert_eq!(network.get_node(1).unwrap().name, "value1");
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn invalid_edits() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        network.rendered_id = 1;
+        let mut history = History::new();
+        history
+            .apply(
+                &mut network,
+                Edit::SetValue(1, 0, PortSlice::new_string(vec!["2.5"])),
+            )
+            .unwrap();
+        assert_eq!(render(&network), 2.5);
+        assert_eq!(
+            history.apply(
+                &mut network,
+                Edit::SetValue(1, 0, PortSlice::new_string(vec!["abc"]))
+            ),
+            Err(NetworkError::InvalidValue(1, 0, "abc".to_owned()))
+        );
+        assert_eq!(
+            history.apply(
+                &mut network,
+                Edit::SetValue(1, 0, PortSlice::new_geometry(vec![]))
+            ),
+            Err(NetworkError::IncompatibleKinds(
+                PortKind::Geometry,
+                PortKind::Float
+            ))
+        );
+        assert_eq!(render(&network), 2.5);
+
+        // Disconnecting an input that is not connected is an error, not an empty step.
+        assert_eq!(
+            history.apply(&mut network, Edit::Disconnect(1, 0)),
+            Err(NetworkError::NotConnected(1, 0))
+        );
+        history.undo(&mut network).unwrap();
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn failed_rollback() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut value = new_node(&repo, 1, "Value", 0, 0).unwrap();
+        // A value that the history can't put back.
+        value.inputs[0].slice = PortSlice::new_string(vec!["abc"]);
+        network.nodes.push(value);
+        let edit = Edit::Group(vec![
+            Edit::SetValue(1, 0, PortSlice::new_float(vec![1.0])),
+            Edit::DeleteNode(9),
+        ]);
+        assert_eq!(
+            edit.apply(&mut network).err(),
+            Some(NetworkError::RollbackFailed(
+                Box::new(NetworkError::NodeNotFound(9)),
+                Box::new(NetworkError::InvalidValue(1, 0, "abc".to_owned()))
+            ))
+        );
+    }
+
+    #[test]
+    fn failed_restore() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Value", 1, 0).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 4, "Negate", 1, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 4, 0).unwrap();
+        let mut history = History::new();
+        history.apply(&mut network, Edit::DeleteNode(1)).unwrap();
+
+        // Change the network behind the history's back: the first connection of the deleted
+        // node would replace this one, the second one can't be made.
+        network.connect(3, 0, 2, 0).unwrap();
+        network.delete_node(4).unwrap();
+        assert_eq!(
+            history.undo(&mut network),
+            Err(NetworkError::NodeNotFound(4))
+        );
+        assert!(network.get_node(1).is_none());
+        assert_eq!(network.connections, vec![Connection::new(3, 0, 2, 0)]);
+    }
+}
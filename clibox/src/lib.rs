@@ -1,18 +1,34 @@
 mod connection;
+mod error;
 mod function;
 mod functions;
+mod geometry;
+mod history;
+mod list_matching;
 mod network;
 mod node;
+mod parallel;
 mod port;
+mod profile;
+mod project;
+mod random;
 mod render_context;
 
 pub use crate::connection::Connection;
-pub use crate::function::Function;
+pub use crate::error::NetworkError;
+pub use crate::function::{Function, FunctionFactory, FunctionInfo, FunctionRepository};
 pub use crate::functions::*;
-pub use crate::network::Network;
+pub use crate::geometry::{Color, Geometry, Path, Point};
+pub use crate::history::{DeletedNode, Edit, History};
+pub use crate::list_matching::{ListMatch, ListMatching};
+pub use crate::network::{Network, PublishedPort};
 pub use crate::node::Node;
-pub use crate::port::{Port, PortDirection, PortKind, PortSlice};
-pub use crate::render_context::RenderContext;
+pub use crate::port::{Port, PortDirection, PortKind, PortRange, PortSlice};
+pub use crate::profile::{NodeStats, RenderStats};
+pub use crate::project::{
+    load_network, network_from_json, network_to_json, save_network, ProjectError, FORMAT_VERSION,
+};
+pub use crate::render_context::{RenderCache, RenderContext};
 
 pub type NodeId = usize;
 pub type PortIndex = usize;
@@ -20,61 +36,36 @@ pub type PortIndex = usize;
 struct NullFunction {}
 impl Function for NullFunction {
     fn setup(&self, _node: &mut Node) {}
-    fn render(&self, _node: &Node, _ctx: &mut RenderContext) {}
-}
-
-pub fn new_function(type_name: &str) -> Option<Box<Function>> {
-    match type_name {
-        "Null" => Some(Box::new(NullFunction {})),
-        "Value" => Some(Box::new(ValueFunction {})),
-        "Add" => Some(Box::new(AddFunction {})),
-        "Parse Floats" => Some(Box::new(ParseFloatsFunction {})),
-        _ => None,
+    fn render(&self, _node: &Node, _ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        Ok(())
     }
 }
 
-pub fn new_node(id: NodeId, type_name: &str, x: i32, y: i32) -> Option<Node> {
-    if let Some(mut function) = new_function(type_name) {
-        let mut node = Node {
-            id,
-            name: type_name.to_owned(),
-            function: Box::new(NullFunction {}),
-            x,
-            y,
-            inputs: Vec::new(),
-            outputs: Vec::new(),
-        };
-        function.setup(&mut node);
-        std::mem::swap(&mut node.function, &mut function);
-        return Some(node);
-    }
-    return None;
+pub fn new_node(
+    repository: &FunctionRepository,
+    id: NodeId,
+    type_name: &str,
+    x: i32,
+    y: i32,
+) -> Option<Node> {
+    let function = repository.new_function(type_name)?;
+    let mut node = Node::new(id, type_name, x, y);
+    function.setup(&mut node);
+    node.function = function;
+    Some(node)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-
-    fn render_single_node(
-        node: Node,
-        output_port_index: PortIndex,
-    ) -> Result<PortSlice, &'static str> {
-        let node_id = node.id;
-        let mut network = Network::new();
-        network.nodes.push(node);
-        network.rendered_id = node_id;
-        let mut ctx = RenderContext::new(&network);
-        // FIXME: ctx.render()
-        network.render(&mut ctx)?;
-        Ok(ctx
-            .get_output_slice(node_id, output_port_index)
-            .unwrap()
-            .clone())
-    }
+    use crate::functions::test_util::render_single_node;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn create_node() {
-        let mut node = new_node(1, "Add", 0, 0).unwrap();
+        let repo = FunctionRepository::with_builtins();
+        let mut node = new_node(&repo, 1, "Add", 0, 0).unwrap();
         assert_eq!(node.name, "Add");
         assert_eq!(node.inputs.len(), 2);
         assert_eq!(node.outputs.len(), 1);
@@ -93,24 +84,49 @@ mod test {
     }
 
     #[test]
-    fn test_parse_floats() {
-        let node = new_node(1, "Parse Floats", 0, 0).unwrap();
-        let results = render_single_node(node, 0).unwrap();
-        assert_eq!(results.size(), 5);
-        assert_eq!(results.get_float(0), 1.0);
-        assert_eq!(results.get_float(1), 2.0);
-        assert_eq!(results.get_float(2), 3.0);
-        assert_eq!(results.get_float(3), 4.0);
-        assert_eq!(results.get_float(4), 5.0);
-        assert_eq!(results.get_float(5), 1.0);
+    fn test_failed_node_skips_downstream() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut bad_floats = new_node(&repo, 1, "Parse Floats", 0, 0).unwrap();
+        bad_floats.set_string("s", 0, "1;x;3");
+        network.nodes.push(bad_floats);
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Parse Floats", 1, 0).unwrap());
+        network.nodes.push(new_node(&repo, 3, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 4, "Value", 0, 2).unwrap());
+        network.connect(1, 0, 3, 0).unwrap();
+        network.connect(2, 0, 3, 1).unwrap();
+        network.connect(3, 0, 4, 0).unwrap();
+        network.rendered_id = 4;
+        let mut ctx = RenderContext::new(&network);
+        let err = network.render(&mut ctx).unwrap_err();
+        assert!(matches!(err, NetworkError::RenderFailed(1, _)));
+        assert_eq!(ctx.get_error(1), Some(&err));
+        assert!(ctx.get_error(2).is_none());
+        assert!(ctx.get_output_slice(2, 0).is_some());
+        assert_eq!(ctx.get_error(3), Some(&NetworkError::UpstreamFailed(3, 1)));
+        assert_eq!(ctx.get_error(4), Some(&NetworkError::UpstreamFailed(4, 1)));
+        assert!(ctx.get_output_slice(4, 0).is_none());
+        let cache = ctx.into_cache();
+
+        // Fixing the value renders the skipped nodes again.
+        network.get_node_mut(1).unwrap().set_string("s", 0, "1;2;3");
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert!(!ctx.rendered.contains(&2));
+        assert_eq!(ctx.get_output_slice(4, 0).unwrap().get_float(0), 2.0);
     }
 
     #[test]
     fn test_network() {
+        let repo = FunctionRepository::with_builtins();
         let mut network = Network::new();
-        let parse_floats_node = new_node(1, "Parse Floats", 0, 0).unwrap();
+        let parse_floats_node = new_node(&repo, 1, "Parse Floats", 0, 0).unwrap();
         network.nodes.push(parse_floats_node);
-        let mut add_node = new_node(2, "Add", 0, 0).unwrap();
+        let mut add_node = new_node(&repo, 2, "Add", 0, 0).unwrap();
         add_node.set_float("b", 0, 100.0);
         network.nodes.push(add_node);
         network.connections.push(Connection::new(1, 0, 2, 0));
@@ -125,14 +141,15 @@ mod test {
 
     #[test]
     fn test_list_matching() {
+        let repo = FunctionRepository::with_builtins();
         let mut network = Network::new();
-        let mut parse_floats_node_1 = new_node(1, "Parse Floats", 0, 0).unwrap();
+        let mut parse_floats_node_1 = new_node(&repo, 1, "Parse Floats", 0, 0).unwrap();
         parse_floats_node_1.set_string("s", 0, "1;2;3;4;5");
         network.nodes.push(parse_floats_node_1);
-        let mut parse_floats_node_2 = new_node(2, "Parse Floats", 1, 0).unwrap();
+        let mut parse_floats_node_2 = new_node(&repo, 2, "Parse Floats", 1, 0).unwrap();
         parse_floats_node_2.set_string("s", 0, "100;200");
         network.nodes.push(parse_floats_node_2);
-        let add_node = new_node(3, "Add", 0, 1).unwrap();
+        let add_node = new_node(&repo, 3, "Add", 0, 1).unwrap();
         network.nodes.push(add_node);
         network.connections.push(Connection::new(1, 0, 3, 0));
         network.connections.push(Connection::new(2, 0, 3, 1));
@@ -147,4 +164,729 @@ mod test {
         assert_eq!(slice.get_float(3), 4.0 + 200.0);
         assert_eq!(slice.get_float(4), 5.0 + 100.0);
     }
+
+    #[test]
+    fn test_incremental_render() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Parse Floats", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.rendered_id = 2;
+
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        assert_eq!(ctx.rendered.len(), 2);
+        let cache = ctx.into_cache();
+
+        // Nothing changed: everything comes from the cache.
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert!(ctx.rendered.is_empty());
+        assert_eq!(ctx.get_output_slice(2, 0).unwrap().get_float(0), 1.0);
+        let cache = ctx.into_cache();
+
+        // Changing a downstream value only recomputes that node.
+        network.get_node_mut(2).unwrap().set_float("b", 0, 10.0);
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert!(!ctx.rendered.contains(&1));
+        assert!(ctx.rendered.contains(&2));
+        assert_eq!(ctx.get_output_slice(2, 0).unwrap().get_float(0), 11.0);
+        let cache = ctx.into_cache();
+
+        // Changing an upstream value recomputes everything downstream.
+        network.get_node_mut(1).unwrap().set_string("s", 0, "5;6");
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert_eq!(ctx.rendered.len(), 2);
+        let slice = ctx.get_output_slice(2, 0).unwrap();
+        assert_eq!(slice.size(), 2);
+        assert_eq!(slice.get_float(1), 16.0);
+    }
+
+    #[test]
+    fn test_shared_upstream_stays_fresh() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Negate", 1, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 3, 0).unwrap();
+        let mut cache = RenderCache::new();
+        for id in &[2, 3] {
+            network.rendered_id = *id;
+            let mut ctx = RenderContext::with_cache(&network, cache);
+            network.render(&mut ctx).unwrap();
+            cache = ctx.into_cache();
+        }
+
+        // Rendering one consumer recomputes the shared node; the other consumer still has to
+        // be recomputed when it is rendered later.
+        network.get_node_mut(1).unwrap().set_float("v", 0, 5.0);
+        network.rendered_id = 3;
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert_eq!(ctx.get_output_slice(3, 0).unwrap().get_float(0), -5.0);
+        let cache = ctx.into_cache();
+        network.rendered_id = 2;
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        network.render(&mut ctx).unwrap();
+        assert!(!ctx.rendered.contains(&1));
+        assert!(ctx.rendered.contains(&2));
+        assert_eq!(ctx.get_output_slice(2, 0).unwrap().get_float(0), 5.0);
+    }
+
+    #[test]
+    fn test_set_input_value() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Negate", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Abs", 0, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        let mut ctx = RenderContext::new(&network);
+        for v in &[2.0, 5.0] {
+            ctx.set_input_value(1, 0, Arc::new(PortSlice::new_float(vec![*v])));
+            let negated = network.render_output(&mut ctx, 1, 0).unwrap();
+            assert_eq!(*negated, PortSlice::new_float(vec![-v]));
+            let abs = network.render_output(&mut ctx, 2, 0).unwrap();
+            assert_eq!(*abs, PortSlice::new_float(vec![*v]));
+        }
+    }
+
+    struct CountingFunction {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Function for CountingFunction {
+        fn setup(&self, node: &mut Node) {
+            node.add_float_input_port("v", vec![1.0]);
+            node.add_float_output_port("out");
+        }
+
+        fn render(&self, node: &Node, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            let in_v = ctx.get_input_slice(node.id, 0);
+            ctx.set_output_slice(node.id, 0, in_v.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_diamond_renders_once() {
+        let repo = FunctionRepository::with_builtins();
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut network = Network::new();
+        let mut source = Node::new(1, "Counter", 0, 0);
+        let function = CountingFunction {
+            count: count.clone(),
+        };
+        function.setup(&mut source);
+        source.function = Box::new(function);
+        network.nodes.push(source);
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Value", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Value", 1, 1).unwrap());
+        network.nodes.push(new_node(&repo, 4, "Add", 0, 2).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 3, 0).unwrap();
+        network.connect(2, 0, 4, 0).unwrap();
+        network.connect(3, 0, 4, 1).unwrap();
+        network.rendered_id = 4;
+        assert_eq!(network.render_order(4).unwrap(), vec![1, 2, 3, 4]);
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(ctx.get_output_slice(4, 0).unwrap().get_float(0), 2.0);
+    }
+
+    #[test]
+    fn test_outputs_are_shared() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut source = new_node(&repo, 1, "Value", 0, 0).unwrap();
+        source.set_float("v", 1, 2.0);
+        network.nodes.push(source);
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Value", 1, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 3, 0).unwrap();
+        let mut ctx = RenderContext::new(&network);
+        network.render_outputs(&mut ctx, &[(2, 0), (3, 0)]).unwrap();
+        let output = ctx.outputs[&(1, 0)].clone();
+        assert!(Arc::ptr_eq(&output, &ctx.inputs[&(2, 0)]));
+        assert!(Arc::ptr_eq(&output, &ctx.inputs[&(3, 0)]));
+        // Value passes its input through without copying it.
+        assert!(Arc::ptr_eq(&output, &ctx.outputs[&(3, 0)]));
+
+        // Changing a shared output copies it first, so the connected inputs keep their values.
+        drop(output);
+        ctx.get_output_slice_mut(1, 0)
+            .unwrap()
+            .append(&PortSlice::new_float(vec![3.0]));
+        assert_eq!(ctx.get_output_slice(1, 0).unwrap().size(), 3);
+        assert_eq!(ctx.get_input_slice(2, 0).size(), 2);
+        assert!(!Arc::ptr_eq(&ctx.outputs[&(1, 0)], &ctx.inputs[&(2, 0)]));
+    }
+
+    #[test]
+    fn test_profiling() {
+        let network = list_matching_network(ListMatching::CrossProduct);
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        assert!(ctx.stats.is_none());
+
+        let mut ctx = RenderContext::new(&network);
+        ctx.enable_profiling();
+        network.render(&mut ctx).unwrap();
+        network.render(&mut ctx).unwrap();
+        let stats = ctx.stats.as_ref().unwrap();
+        let add = stats.get(3).unwrap();
+        assert_eq!(add.name, "Add");
+        assert_eq!(add.type_name, "Add");
+        // The second render reuses the cached outputs.
+        assert_eq!(add.invocations, 1);
+        assert_eq!(add.input_sizes, vec![5, 2]);
+        assert_eq!(add.output_sizes, vec![10]);
+        assert_eq!(stats.get(2).unwrap().output_sizes, vec![2]);
+        let report = stats.report();
+        assert_eq!(report.len(), 3);
+        assert!(report.windows(2).all(|w| w[0].time >= w[1].time));
+        let table = stats.to_string();
+        assert_eq!(table.lines().count(), 5);
+        assert!(table.lines().any(|line| line.starts_with("Parse Floats")));
+    }
+
+    /// Many independent branches that are combined at the end, with a failing branch that is
+    /// not needed by the rendered node.
+    fn branching_network() -> Network {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut combine = new_node(&repo, 1, "Combine", 0, 10).unwrap();
+        combine.name = "combine".to_owned();
+        network.nodes.push(combine);
+        for branch in 0..3 {
+            let id = 10 + branch * 10;
+            let mut random = new_node(&repo, id, "Random Numbers", branch as i32, 0).unwrap();
+            random.set_int("seed", 0, branch as i32);
+            random.set_int("amount", 0, 1000);
+            network.nodes.push(random);
+            let mut group = new_node(&repo, id + 1, "Group", branch as i32, 1).unwrap();
+            group.set_int("size", 0, 10);
+            network.nodes.push(group);
+            let mut multiply = new_node(&repo, id + 2, "Multiply", branch as i32, 2).unwrap();
+            multiply.set_float("b", 0, 1.5);
+            network.nodes.push(multiply);
+            network
+                .nodes
+                .push(new_node(&repo, id + 3, "Sum", branch as i32, 3).unwrap());
+            network.connect(id, 0, id + 1, 0).unwrap();
+            network.connect(id + 1, 0, id + 2, 0).unwrap();
+            network.connect(id + 2, 0, id + 3, 0).unwrap();
+            network.connect(id + 3, 0, 1, branch).unwrap();
+        }
+        let mut divide = new_node(&repo, 2, "Divide", 5, 0).unwrap();
+        divide.set_float("b", 0, 0.0);
+        network.nodes.push(divide);
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Negate", 5, 1).unwrap());
+        network.connect(2, 0, 3, 0).unwrap();
+        network.rendered_id = 1;
+        network
+    }
+
+    #[test]
+    fn test_parallel_render() {
+        let network = branching_network();
+        let render = |threads: usize, targets: &[(NodeId, PortIndex)]| {
+            let mut ctx = RenderContext::new(&network);
+            ctx.threads = threads;
+            ctx.enable_profiling();
+            let result = network.render_outputs(&mut ctx, targets);
+            let mut outputs: Vec<_> = ctx.outputs.iter().collect();
+            outputs.sort_by_key(|(key, _)| **key);
+            let mut errors: Vec<_> = ctx.errors.iter().collect();
+            errors.sort_by_key(|(id, _)| **id);
+            let invocations: usize = ctx
+                .stats
+                .as_ref()
+                .unwrap()
+                .report()
+                .iter()
+                .map(|stats| stats.invocations)
+                .sum();
+            format!("{:?} {:?} {:?} {}", result, outputs, errors, invocations)
+        };
+        let targets = [(1, 0), (3, 0)];
+        let sequential = render(1, &targets);
+        assert!(sequential.contains("RenderFailed(2"));
+        assert!(sequential.contains("UpstreamFailed(3, 2)"));
+        for threads in &[2, 4, 32] {
+            assert_eq!(render(*threads, &targets), sequential);
+            assert_eq!(render(*threads, &[(1, 0)]), render(1, &[(1, 0)]));
+        }
+
+        // Cached outputs are reused in the same way.
+        let mut ctx = RenderContext::new(&network);
+        ctx.threads = 4;
+        let first = network.render_output(&mut ctx, 1, 0).unwrap();
+        network.render_output(&mut ctx, 1, 0).unwrap();
+        assert!(ctx.rendered.is_empty());
+        let mut ctx = RenderContext::new(&network);
+        assert_eq!(network.render_output(&mut ctx, 1, 0).unwrap(), first);
+    }
+
+    struct PanickingFunction {}
+    impl Function for PanickingFunction {
+        fn setup(&self, node: &mut Node) {
+            node.add_float_output_port("out");
+        }
+
+        fn render(&self, _node: &Node, _ctx: &mut RenderContext) -> Result<(), NetworkError> {
+            panic!("render panicked");
+        }
+    }
+
+    #[test]
+    fn test_parallel_render_panic() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut node = Node::new(1, "Panic", 0, 0);
+        let function = PanickingFunction {};
+        function.setup(&mut node);
+        node.function = Box::new(function);
+        network.nodes.push(node);
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Value", 1, 0).unwrap());
+        network.nodes.push(new_node(&repo, 3, "Add", 0, 1).unwrap());
+        network.connect(1, 0, 3, 0).unwrap();
+        network.connect(2, 0, 3, 1).unwrap();
+        network.rendered_id = 3;
+        // The panic reaches the caller instead of leaving it waiting for the worker.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut ctx = RenderContext::new(&network);
+            ctx.threads = 2;
+            network.render_output(&mut ctx, 3, 0)
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let repo = FunctionRepository::with_builtins();
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut network = Network::new();
+        let mut source = Node::new(1, "Counter", 0, 0);
+        let function = CountingFunction {
+            count: count.clone(),
+        };
+        function.setup(&mut source);
+        source.function = Box::new(function);
+        network.nodes.push(source);
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Frame", 1, 0).unwrap());
+        network.nodes.push(new_node(&repo, 3, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 4, "Time", 2, 0).unwrap());
+        network.connect(1, 0, 3, 0).unwrap();
+        network.connect(2, 0, 3, 1).unwrap();
+        network.rendered_id = 3;
+        for threads in &[1, 2] {
+            count.store(0, Ordering::SeqCst);
+            let mut ctx = RenderContext::new(&network);
+            ctx.threads = *threads;
+            let results: Vec<f32> = network
+                .render_frames(&mut ctx, 0..3)
+                .into_iter()
+                .map(|r| r.unwrap().get_float(0))
+                .collect();
+            assert_eq!(results, vec![1.0, 2.0, 3.0]);
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+        }
+
+        network.rendered_id = 4;
+        let mut ctx = RenderContext::new(&network);
+        ctx.fps = 10.0;
+        let times: Vec<f32> = network
+            .render_frames(&mut ctx, 10..12)
+            .into_iter()
+            .map(|r| r.unwrap().get_float(0))
+            .collect();
+        assert_eq!(times, vec![1.0, 1.1]);
+
+        // A subnetwork that contains a time dependent node depends on time itself.
+        let mut inner = Network::new();
+        inner.nodes.push(new_node(&repo, 1, "Frame", 0, 0).unwrap());
+        inner.rendered_id = 1;
+        let subnetwork = new_subnetwork_node(1, "sub", inner, 0, 0);
+        assert!(subnetwork.is_time_dependent());
+        let mut network = Network::new();
+        network.nodes.push(subnetwork);
+        network.rendered_id = 1;
+        let mut ctx = RenderContext::new(&network);
+        let frames: Vec<PortSlice> = network
+            .render_frames(&mut ctx, 5..7)
+            .into_iter()
+            .map(|r| (*r.unwrap()).clone())
+            .collect();
+        assert_eq!(
+            frames,
+            vec![PortSlice::new_int(vec![5]), PortSlice::new_int(vec![6])]
+        );
+    }
+
+    #[test]
+    fn test_render_outputs() {
+        let repo = FunctionRepository::with_builtins();
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut network = Network::new();
+        let mut source = Node::new(1, "Counter", 0, 0);
+        let function = CountingFunction {
+            count: count.clone(),
+        };
+        function.setup(&mut source);
+        source.function = Box::new(function);
+        network.nodes.push(source);
+        let mut add = new_node(&repo, 2, "Add", 0, 1).unwrap();
+        add.set_float("b", 0, 10.0);
+        network.nodes.push(add);
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Negate", 1, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 3, 0).unwrap();
+        network.rendered_id = 2;
+
+        let mut ctx = RenderContext::new(&network);
+        let slices = network
+            .render_outputs(&mut ctx, &[(2, 0), (3, 0), (1, 0)])
+            .unwrap();
+        assert_eq!(
+            slices,
+            vec![
+                Arc::new(PortSlice::new_float(vec![11.0])),
+                Arc::new(PortSlice::new_float(vec![-1.0])),
+                Arc::new(PortSlice::new_float(vec![1.0]))
+            ]
+        );
+        // The results are the cached outputs, not copies of them.
+        assert!(Arc::ptr_eq(&slices[2], &ctx.outputs[&(1, 0)]));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(network.render_order_of(&[2, 3]).unwrap(), vec![1, 2, 3]);
+
+        assert_eq!(
+            *network.render_output(&mut ctx, 3, 0).unwrap(),
+            PortSlice::new_float(vec![-1.0])
+        );
+        assert_eq!(
+            network.render_output(&mut ctx, 3, 1),
+            Err(NetworkError::OutputPortNotFound(3, 1))
+        );
+        assert_eq!(
+            network.render_output(&mut ctx, 9, 0),
+            Err(NetworkError::NodeNotFound(9))
+        );
+    }
+
+    #[test]
+    fn test_render_outputs_after_change() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 3, "Negate", 1, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(1, 0, 3, 0).unwrap();
+        let mut ctx = RenderContext::new(&network);
+        network.render_outputs(&mut ctx, &[(2, 0), (3, 0)]).unwrap();
+        let cache = ctx.into_cache();
+
+        // Render the targets one after another in the same context after changing the node
+        // they share.
+        network.get_node_mut(1).unwrap().set_float("v", 0, 5.0);
+        let mut ctx = RenderContext::with_cache(&network, cache);
+        let negated = network.render_output(&mut ctx, 3, 0).unwrap();
+        let added = network.render_output(&mut ctx, 2, 0).unwrap();
+        assert_eq!(*negated, PortSlice::new_float(vec![-5.0]));
+        assert_eq!(*added, PortSlice::new_float(vec![5.0]));
+        assert_eq!(
+            network.render_outputs(&mut ctx, &[(2, 0), (3, 0)]).unwrap(),
+            vec![added, negated]
+        );
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 0, "Parse Floats", 0, 0).unwrap());
+        for id in 1..2000 {
+            let mut node = new_node(&repo, id, "Add", 0, id as i32).unwrap();
+            node.set_float("b", 0, 1.0);
+            network.nodes.push(node);
+            network.connections.push(Connection::new(id - 1, 0, id, 0));
+        }
+        network.rendered_id = 1999;
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let slice = ctx.get_output_slice(1999, 0).unwrap();
+        assert_eq!(slice.get_float(0), 2000.0);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network.nodes.push(new_node(&repo, 1, "Add", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(2, 0, 1, 0).unwrap();
+        network.rendered_id = 2;
+        let mut ctx = RenderContext::new(&network);
+        assert_eq!(network.render(&mut ctx), Err(NetworkError::Cycle(2)));
+    }
+
+    #[test]
+    fn test_function_repository() {
+        let mut repo = FunctionRepository::with_builtins();
+        assert!(repo.contains("Add"));
+        assert!(new_node(&repo, 1, "Counter", 0, 0).is_none());
+        let count = Arc::new(AtomicUsize::new(0));
+        repo.register("Counter", "test", "Counts renders.", move || {
+            Box::new(CountingFunction {
+                count: count.clone(),
+            })
+        });
+        let node = new_node(&repo, 1, "Counter", 0, 0).unwrap();
+        assert_eq!(node.inputs.len(), 1);
+        assert_eq!(
+            repo.get_info("Counter").unwrap().description,
+            "Counts renders."
+        );
+        assert!(repo.categories().contains(&"test"));
+        let math: Vec<&str> = repo
+            .function_infos_in_category("math")
+            .iter()
+            .map(|info| info.type_name.as_str())
+            .collect();
+        assert!(math.contains(&"Add"));
+        assert!(!math.contains(&"Counter"));
+        let mut sorted = math.clone();
+        sorted.sort();
+        assert_eq!(math, sorted);
+    }
+
+    fn list_matching_network(list_matching: ListMatching) -> Network {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut parse_floats_node_1 = new_node(&repo, 1, "Parse Floats", 0, 0).unwrap();
+        parse_floats_node_1.set_string("s", 0, "1;2;3;4;5");
+        network.nodes.push(parse_floats_node_1);
+        let mut parse_floats_node_2 = new_node(&repo, 2, "Parse Floats", 1, 0).unwrap();
+        parse_floats_node_2.set_string("s", 0, "100;200");
+        network.nodes.push(parse_floats_node_2);
+        let mut add_node = new_node(&repo, 3, "Add", 0, 1).unwrap();
+        add_node.set_list_matching(list_matching);
+        network.nodes.push(add_node);
+        network.connections.push(Connection::new(1, 0, 3, 0));
+        network.connections.push(Connection::new(2, 0, 3, 1));
+        network.rendered_id = 3;
+        network
+    }
+
+    #[test]
+    fn test_list_matching_shortest() {
+        let network = list_matching_network(ListMatching::Shortest);
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let slice = ctx.get_output_slice(network.rendered_id, 0).unwrap();
+        assert_eq!(slice.size(), 2);
+        assert_eq!(slice.get_float(0), 1.0 + 100.0);
+        assert_eq!(slice.get_float(1), 2.0 + 200.0);
+    }
+
+    #[test]
+    fn test_list_matching_cross_product() {
+        let network = list_matching_network(ListMatching::CrossProduct);
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let slice = ctx.get_output_slice(network.rendered_id, 0).unwrap();
+        assert_eq!(slice.size(), 10);
+        assert_eq!(slice.get_float(0), 1.0 + 100.0);
+        assert_eq!(slice.get_float(1), 1.0 + 200.0);
+        assert_eq!(slice.get_float(2), 2.0 + 100.0);
+        assert_eq!(slice.get_float(9), 5.0 + 200.0);
+    }
+
+    #[test]
+    fn test_list_port() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Parse Floats", 0, 0).unwrap());
+        network.nodes.push(new_node(&repo, 2, "Sum", 0, 1).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.rendered_id = 2;
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let slice = ctx.get_output_slice(2, 0).unwrap();
+        assert_eq!(slice.size(), 1);
+        assert_eq!(slice.get_float(0), 15.0);
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Parse Floats", 0, 0).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Group", 0, 1).unwrap());
+        let mut add_node = new_node(&repo, 3, "Add", 0, 2).unwrap();
+        add_node.set_float("b", 0, 100.0);
+        network.nodes.push(add_node);
+        network.nodes.push(new_node(&repo, 4, "Sum", 0, 3).unwrap());
+        network
+            .nodes
+            .push(new_node(&repo, 5, "Flatten", 0, 4).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(2, 0, 3, 0).unwrap();
+        network.connect(3, 0, 4, 0).unwrap();
+        network.connect(4, 0, 5, 0).unwrap();
+        network.rendered_id = 5;
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+
+        // Add is rendered for each group, keeping the grouping.
+        let added = ctx.get_output_slice(3, 0).unwrap();
+        assert_eq!(added.depth(), 2);
+        assert_eq!(added.sublist(0), PortSlice::new_float(vec![101.0, 102.0]));
+        assert_eq!(added.sublist(2), PortSlice::new_float(vec![105.0]));
+
+        // The list port of Sum receives one group at a time.
+        let sums = ctx.get_output_slice(5, 0).unwrap();
+        assert_eq!(sums, &PortSlice::new_float(vec![203.0, 207.0, 105.0]));
+    }
+
+    #[test]
+    fn test_subnetwork() {
+        let repo = FunctionRepository::with_builtins();
+        let mut inner = Network::new();
+        inner.nodes.push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        let mut add_node = new_node(&repo, 2, "Add", 0, 1).unwrap();
+        add_node.set_float("b", 0, 1.0);
+        inner.nodes.push(add_node);
+        inner.connect(1, 0, 2, 0).unwrap();
+        inner.rendered_id = 2;
+        inner.publish_input("value", 1, 0).unwrap();
+        inner.publish_input("amount", 2, 1).unwrap();
+        assert_eq!(
+            inner.publish_input("nope", 3, 0),
+            Err(NetworkError::NodeNotFound(3))
+        );
+
+        let mut subnet = new_subnetwork_node(2, "Add Amount", inner, 0, 1);
+        assert_eq!(subnet.inputs.len(), 2);
+        assert_eq!(subnet.inputs[0].name, "value");
+        assert_eq!(subnet.inputs[1].get_float(0), 1.0);
+        assert_eq!(subnet.outputs.len(), 1);
+        subnet.set_float("amount", 0, 10.0);
+
+        let mut network = Network::new();
+        network
+            .nodes
+            .push(new_node(&repo, 1, "Parse Floats", 0, 0).unwrap());
+        network.nodes.push(subnet);
+        network.connect(1, 0, 2, 0).unwrap();
+        network.rendered_id = 2;
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let slice = ctx.get_output_slice(2, 0).unwrap();
+        assert_eq!(
+            slice,
+            &PortSlice::new_float(vec![11.0, 12.0, 13.0, 14.0, 15.0])
+        );
+
+        // A published input whose port is gone makes rendering fail instead of panicking.
+        let mut inner = Network::new();
+        inner.nodes.push(new_node(&repo, 1, "Add", 0, 0).unwrap());
+        inner.rendered_id = 1;
+        inner.published_inputs.push(PublishedPort {
+            name: "gone".to_owned(),
+            node_id: 1,
+            port: 5,
+        });
+        let subnet = new_subnetwork_node(1, "broken", inner, 0, 0);
+        assert!(subnet.inputs.is_empty());
+        assert_eq!(
+            render_single_node(subnet, 0),
+            Err(NetworkError::RenderFailed(
+                1,
+                NetworkError::InputPortNotFound(1, 5).to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_kind_conversion() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut strings = Node::new(1, "strings1", 0, 0);
+        strings.add_string_input_port("s", vec!["1", "2", "x"]);
+        strings.add_string_output_port("out");
+        strings.function = Box::new(ValueFunction {});
+        network.nodes.push(strings);
+        network
+            .nodes
+            .push(new_node(&repo, 2, "Group", 0, 1).unwrap());
+        network.nodes.push(new_node(&repo, 3, "Add", 0, 2).unwrap());
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(2, 0, 3, 0).unwrap();
+        network.rendered_id = 3;
+        let mut ctx = RenderContext::new(&network);
+        // The float input of Add parses the strings. Strings that aren't numbers are an error.
+        assert_eq!(
+            network.render(&mut ctx),
+            Err(NetworkError::RenderFailed(
+                3,
+                "Could not convert \"x\" on input \"a\" to Float.".to_owned()
+            ))
+        );
+        // Generic ports pass the strings on unchanged.
+        let groups = ctx.get_output_slice(2, 0).unwrap();
+        assert_eq!(groups.kind(), PortKind::String);
+
+        network.get_node_mut(1).unwrap().set_string("s", 2, " 3.5");
+        let mut ctx = RenderContext::new(&network);
+        network.render(&mut ctx).unwrap();
+        let added = ctx.get_output_slice(3, 0).unwrap();
+        assert_eq!(added.flatten(), PortSlice::new_float(vec![1.0, 2.0, 3.5]));
+    }
 }
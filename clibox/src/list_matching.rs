@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+/// How the lists of a node's inputs are combined when its function works element by element.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub enum ListMatching {
+    /// Iterate over the longest list, wrapping around the shorter ones.
+    #[default]
+    Longest,
+    /// Iterate over the shortest list, ignoring the extra values of the longer ones.
+    Shortest,
+    /// Combine every value of each list with every value of the others.
+    CrossProduct,
+}
+
+/// The iterations for a set of input list sizes.
+///
+/// A node without value inputs is called once; a node with an empty value input is not called at all.
+pub struct ListMatch {
+    matching: ListMatching,
+    sizes: Vec<Option<usize>>,
+    len: usize,
+}
+
+impl ListMatch {
+    pub fn new(matching: ListMatching, sizes: Vec<usize>) -> ListMatch {
+        ListMatch::with_list_ports(matching, sizes.into_iter().map(Some).collect())
+    }
+
+    /// Inputs without a size are list ports: the function receives them whole,
+    /// so they don't take part in the matching.
+    pub fn with_list_ports(matching: ListMatching, sizes: Vec<Option<usize>>) -> ListMatch {
+        let value_sizes: Vec<usize> = sizes.iter().filter_map(|s| *s).collect();
+        let len = if value_sizes.contains(&0) {
+            0
+        } else if value_sizes.is_empty() {
+            1
+        } else {
+            match matching {
+                ListMatching::Longest => value_sizes.iter().cloned().max().unwrap(),
+                ListMatching::Shortest => value_sizes.iter().cloned().min().unwrap(),
+                ListMatching::CrossProduct => value_sizes.iter().product(),
+            }
+        };
+        ListMatch {
+            matching,
+            sizes,
+            len,
+        }
+    }
+
+    /// The number of times the function should be called.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The index into the list of the given input for the given iteration.
+    /// List ports always return 0.
+    pub fn index(&self, iteration: usize, input: usize) -> usize {
+        let size = match self.sizes[input] {
+            Some(size) => size,
+            None => return 0,
+        };
+        match self.matching {
+            ListMatching::Longest | ListMatching::Shortest => iteration % size,
+            ListMatching::CrossProduct => {
+                // The first input changes slowest, the last input changes fastest.
+                let stride: usize = self.sizes[input + 1..].iter().filter_map(|s| *s).product();
+                (iteration / stride) % size
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lengths() {
+        let sizes = vec![5, 2];
+        assert_eq!(
+            ListMatch::new(ListMatching::Longest, sizes.clone()).len(),
+            5
+        );
+        assert_eq!(
+            ListMatch::new(ListMatching::Shortest, sizes.clone()).len(),
+            2
+        );
+        assert_eq!(ListMatch::new(ListMatching::CrossProduct, sizes).len(), 10);
+        assert_eq!(ListMatch::new(ListMatching::Longest, vec![]).len(), 1);
+        assert!(ListMatch::new(ListMatching::CrossProduct, vec![3, 0]).is_empty());
+    }
+
+    #[test]
+    fn cross_product_indices() {
+        let m = ListMatch::new(ListMatching::CrossProduct, vec![2, 3]);
+        let pairs: Vec<(usize, usize)> = (0..m.len())
+            .map(|i| (m.index(i, 0), m.index(i, 1)))
+            .collect();
+        assert_eq!(pairs, vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn list_ports() {
+        let m = ListMatch::with_list_ports(ListMatching::Shortest, vec![Some(4), None, Some(3)]);
+        assert_eq!(m.len(), 3);
+        let m =
+            ListMatch::with_list_ports(ListMatching::CrossProduct, vec![Some(2), Some(0), None]);
+        assert!(m.is_empty());
+        let m =
+            ListMatch::with_list_ports(ListMatching::CrossProduct, vec![Some(2), None, Some(2)]);
+        assert_eq!(m.len(), 4);
+        assert_eq!(m.index(1, 0), 0);
+        assert_eq!(m.index(1, 2), 1);
+        assert_eq!(m.index(2, 0), 1);
+        let m = ListMatch::with_list_ports(ListMatching::Longest, vec![None]);
+        assert_eq!(m.len(), 1);
+    }
+}
@@ -0,0 +1,320 @@
+use std::env;
+use std::process;
+
+use clibox::{
+    load_network, FunctionRepository, Network, NetworkError, NodeId, PortKind, PortSlice,
+    RenderContext, RenderStats,
+};
+use serde_json::json;
+
+const USAGE: &str =
+    "Usage: clibox PROJECT_FILE [--node NODE] [--set NODE.PORT=VALUE]... [--format text|csv|json] [--threads N] [--profile]
+
+Renders a network file and prints the output of the rendered node.
+
+Options:
+  --node NODE             Render the node with this name or id instead of the rendered node.
+  --set NODE.PORT=VALUE   Override an input port value. Separate multiple values with ';'.
+  --format FORMAT         Print the output as text (one value per line), csv or json.
+  --threads N             Render independent nodes on N threads at the same time.
+  --profile               Print the render time and list sizes of every node to stderr.";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    path: String,
+    node: Option<String>,
+    overrides: Vec<(String, String, String)>,
+    format: Format,
+    threads: usize,
+    profile: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut path = None;
+    let mut node = None;
+    let mut overrides = Vec::new();
+    let mut format = Format::Text;
+    let mut threads = 1;
+    let mut profile = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--node" => {
+                node = Some(args.next().ok_or("--node needs a node name.")?.clone());
+            }
+            "--set" => {
+                let assignment = args.next().ok_or("--set needs NODE.PORT=VALUE.")?;
+                overrides.push(parse_assignment(assignment)?);
+            }
+            "--format" => {
+                format = match args.next().map(|s| s.as_str()) {
+                    Some("text") => Format::Text,
+                    Some("csv") => Format::Csv,
+                    Some("json") => Format::Json,
+                    _ => return Err("--format should be text, csv or json.".to_owned()),
+                }
+            }
+            "--threads" => {
+                threads = match args.next().map(|s| s.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => n,
+                    _ => return Err("--threads needs a number larger than 0.".to_owned()),
+                }
+            }
+            "--profile" => profile = true,
+            s if s.starts_with("--") => return Err(format!("Unknown option {}.", s)),
+            s => {
+                if path.is_some() {
+                    return Err("Only one project file can be rendered.".to_owned());
+                }
+                path = Some(s.to_owned());
+            }
+        }
+    }
+    Ok(Options {
+        path: path.ok_or("No project file given.")?,
+        node,
+        overrides,
+        format,
+        threads,
+        profile,
+    })
+}
+
+/// Split "add1.b=100" into the node, the port and the value.
+fn parse_assignment(assignment: &str) -> Result<(String, String, String), String> {
+    let invalid = || format!("Invalid --set {}, expected NODE.PORT=VALUE.", assignment);
+    let equals = assignment.find('=').ok_or_else(invalid)?;
+    let (target, value) = (&assignment[..equals], &assignment[equals + 1..]);
+    let dot = target.rfind('.').ok_or_else(invalid)?;
+    let (node, port) = (&target[..dot], &target[dot + 1..]);
+    if node.is_empty() || port.is_empty() {
+        return Err(invalid());
+    }
+    Ok((node.to_owned(), port.to_owned(), value.to_owned()))
+}
+
+/// Find a node by name, or by id if no node has that name.
+fn find_node(network: &Network, name: &str) -> Option<NodeId> {
+    if let Some(node) = network.get_node_by_name(name) {
+        return Some(node.id);
+    }
+    let id = name.parse::<NodeId>().ok()?;
+    network.get_node(id).map(|n| n.id)
+}
+
+/// Values are parsed as the kind of the port. Generic ports accept any kind: their values are
+/// parsed as the kind of the value they hold, or as strings if that fails.
+fn apply_override(
+    network: &mut Network,
+    node_name: &str,
+    port_name: &str,
+    value: &str,
+) -> Result<(), String> {
+    let id = find_node(network, node_name).ok_or(format!("Node {} not found.", node_name))?;
+    let node = network.get_node_mut(id).unwrap();
+    let port = node
+        .get_input_by_name_mut(port_name)
+        .ok_or(format!("Node {} has no input {}.", node_name, port_name))?;
+    let parsed = if port.generic {
+        PortSlice::parse(port.slice.kind(), value)
+            .or_else(|| PortSlice::parse(PortKind::String, value))
+    } else {
+        PortSlice::parse(port.kind, value)
+    };
+    port.slice = parsed.ok_or(format!(
+        "Invalid value {} for {}.{}.",
+        value, node_name, port_name
+    ))?;
+    node.mark_dirty();
+    Ok(())
+}
+
+fn format_text(slice: &PortSlice, out: &mut String) {
+    if slice.is_nested() {
+        for i in 0..slice.size() {
+            if i > 0 {
+                out.push('\n');
+            }
+            format_text(&slice.sublist(i), out);
+        }
+    } else {
+        for i in 0..slice.size() {
+            out.push_str(&slice.get_string(i));
+            out.push('\n');
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// One value per row. Values of nested lists are prefixed with the index of their sublist.
+fn format_csv(slice: &PortSlice, prefix: &str, out: &mut String) {
+    if slice.is_nested() {
+        for i in 0..slice.size() {
+            format_csv(&slice.sublist(i), &format!("{}{},", prefix, i), out);
+        }
+    } else {
+        for i in 0..slice.size() {
+            out.push_str(prefix);
+            out.push_str(&csv_field(&slice.get_string(i)));
+            out.push('\n');
+        }
+    }
+}
+
+fn to_json(slice: &PortSlice) -> serde_json::Value {
+    let values = (0..slice.size()).map(|i| match slice {
+        PortSlice::Int(vals) => json!(vals[i]),
+        PortSlice::Float(vals) => json!(vals[i]),
+        PortSlice::String(vals) => json!(vals[i]),
+        PortSlice::Bool(vals) => json!(vals[i]),
+        PortSlice::Point(vals) => json!([vals[i].x, vals[i].y]),
+        PortSlice::Color(vals) => json!(vals[i].to_hex()),
+        PortSlice::Geometry(vals) => json!(vals[i].to_string()),
+        PortSlice::List(_, lists) => to_json(&lists[i]),
+    });
+    serde_json::Value::Array(values.collect())
+}
+
+fn format_slice(slice: &PortSlice, format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Text => format_text(slice, &mut out),
+        Format::Csv => format_csv(slice, "", &mut out),
+        Format::Json => {
+            out = to_json(slice).to_string();
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Returns the formatted output, and the render statistics if profiling was requested.
+fn run(options: &Options) -> Result<(String, Option<RenderStats>), String> {
+    let repository = FunctionRepository::with_builtins();
+    let mut network = load_network(&options.path, &repository)
+        .map_err(|err| format!("Error when opening {}: {}", options.path, err))?;
+    for (node, port, value) in &options.overrides {
+        apply_override(&mut network, node, port, value)?;
+    }
+    let id = match &options.node {
+        Some(name) => find_node(&network, name).ok_or(format!("Node {} not found.", name))?,
+        None => network.rendered_id,
+    };
+    if network.get_node(id).is_none() {
+        return Err(NetworkError::NoRenderedNode.to_string());
+    }
+    let mut ctx = RenderContext::new(&network);
+    ctx.threads = options.threads;
+    if options.profile {
+        ctx.enable_profiling();
+    }
+    let slice = network
+        .render_output(&mut ctx, id, 0)
+        .map_err(|err| err.to_string())?;
+    Ok((format_slice(&slice, options.format), ctx.stats))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    match run(&options) {
+        Ok((output, stats)) => {
+            print!("{}", output);
+            if let Some(stats) = stats {
+                eprint!("{}", stats);
+            }
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn arguments() {
+        let options = parse_args(&args("net.json --set add1.b=100;200 --format csv")).unwrap();
+        assert_eq!(options.path, "net.json");
+        assert_eq!(options.format, Format::Csv);
+        assert!(!options.profile);
+        assert_eq!(options.threads, 1);
+        assert_eq!(
+            parse_args(&args("net.json --threads 8")).unwrap().threads,
+            8
+        );
+        assert!(parse_args(&args("net.json --threads 0")).is_err());
+        assert!(parse_args(&args("net.json --profile")).unwrap().profile);
+        assert_eq!(
+            options.overrides,
+            vec![("add1".to_owned(), "b".to_owned(), "100;200".to_owned())]
+        );
+        assert!(parse_args(&args("net.json --set add1=100")).is_err());
+        assert!(parse_args(&args("net.json --format xml")).is_err());
+        assert!(parse_args(&args("--node add1")).is_err());
+    }
+
+    #[test]
+    fn overrides() {
+        let repo = FunctionRepository::with_builtins();
+        let mut network = Network::new();
+        let mut cull = clibox::new_node(&repo, 1, "Cull", 0, 0).unwrap();
+        cull.name = "cull1".to_owned();
+        network.nodes.push(cull);
+        let mut add = clibox::new_node(&repo, 2, "Add", 0, 0).unwrap();
+        add.name = "add1".to_owned();
+        network.nodes.push(add);
+        let value =
+            |network: &Network, id: NodeId| network.get_node(id).unwrap().inputs[0].slice.clone();
+
+        // Generic ports keep the kind of their value if they can, and take strings otherwise.
+        apply_override(&mut network, "cull1", "list", "1;2").unwrap();
+        assert_eq!(value(&network, 1), PortSlice::new_float(vec![1.0, 2.0]));
+        apply_override(&mut network, "cull1", "list", "a;b").unwrap();
+        assert_eq!(value(&network, 1), PortSlice::new_string(vec!["a", "b"]));
+        apply_override(&mut network, "add1", "a", "3").unwrap();
+        assert_eq!(value(&network, 2), PortSlice::new_float(vec![3.0]));
+        assert!(apply_override(&mut network, "add1", "a", "a;b").is_err());
+    }
+
+    #[test]
+    fn formats() {
+        let slice = PortSlice::new_float(vec![1.0, 2.5]).group(1);
+        assert_eq!(format_slice(&slice, Format::Text), "1\n\n2.5\n");
+        assert_eq!(format_slice(&slice, Format::Csv), "0,1\n1,2.5\n");
+        assert_eq!(format_slice(&slice, Format::Json), "[[1.0],[2.5]]\n");
+        let points = PortSlice::parse(PortKind::Point, "1,2").unwrap();
+        assert_eq!(format_slice(&points, Format::Csv), "\"1,2\"\n");
+    }
+}
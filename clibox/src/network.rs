@@ -1,109 +1,507 @@
-use crate::{Connection, Node, NodeId, Port, PortIndex, RenderContext};
+use crate::parallel::Workers;
+use crate::project::copy_node;
+use crate::{
+    Connection, FunctionRepository, ListMatch, NetworkError, Node, NodeId, Port, PortIndex,
+    PortRange, PortSlice, RenderContext,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+/// An input port of an inner node that is exposed as an input of the subnetwork node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PublishedPort {
+    pub name: String,
+    pub node_id: NodeId,
+    pub port: PortIndex,
+}
+
+#[derive(Default)]
 pub struct Network {
     pub rendered_id: NodeId,
     pub nodes: Vec<Node>,
     pub connections: Vec<Connection>,
+    pub published_inputs: Vec<PublishedPort>,
 }
 
 impl Network {
     pub fn new() -> Network {
-        Network {
-            rendered_id: 0,
-            nodes: Vec::new(),
-            connections: Vec::new(),
+        Network::default()
+    }
+
+    /// Expose an input port of one of the nodes under the given name, so it becomes an input of
+    /// the node when the network is used as a subnetwork. Publishing an existing name replaces it.
+    pub fn publish_input(
+        &mut self,
+        name: &str,
+        node_id: NodeId,
+        port: PortIndex,
+    ) -> Result<(), NetworkError> {
+        if self.get_node(node_id).is_none() {
+            return Err(NetworkError::NodeNotFound(node_id));
         }
+        if self.get_input_port(node_id, port).is_none() {
+            return Err(NetworkError::InputPortNotFound(node_id, port));
+        }
+        self.unpublish_input(name);
+        self.published_inputs.push(PublishedPort {
+            name: name.to_owned(),
+            node_id,
+            port,
+        });
+        Ok(())
     }
 
+    pub fn unpublish_input(&mut self, name: &str) {
+        self.published_inputs.retain(|p| p.name != name);
+    }
+
+    /// Connect an output to an input. An input has at most one connection, so an existing
+    /// connection to the input is replaced.
     pub fn connect(
         &mut self,
         output_id: NodeId,
         output_port: PortIndex,
         input_id: NodeId,
         input_port: PortIndex,
-    ) -> Result<(), &'static str> {
-        let output_node = self.get_node(output_id);
-        if output_node.is_none() {
-            return Err("Output node could not be found.");
+    ) -> Result<(), NetworkError> {
+        if self.get_node(output_id).is_none() {
+            return Err(NetworkError::NodeNotFound(output_id));
         }
-        let input_node = self.get_node(input_id);
-        if input_node.is_none() {
-            return Err("Input node could not be found.");
+        if self.get_node(input_id).is_none() {
+            return Err(NetworkError::NodeNotFound(input_id));
         }
         if self.get_output_port(output_id, output_port).is_none() {
-            return Err("Output port could not be found.");
+            return Err(NetworkError::OutputPortNotFound(output_id, output_port));
         }
         if self.get_input_port(input_id, input_port).is_none() {
-            return Err("Input port could not be found.");
+            return Err(NetworkError::InputPortNotFound(input_id, input_port));
         }
+        let output = self.get_output_port(output_id, output_port).unwrap();
+        let input = self.get_input_port(input_id, input_port).unwrap();
+        if !output.can_connect_to(input) {
+            return Err(NetworkError::IncompatibleKinds(output.kind, input.kind));
+        }
+        self.connections
+            .retain(|c| !(c.input_id == input_id && c.input_port == input_port));
         let conn = Connection::new(output_id, output_port, input_id, input_port);
         self.connections.push(conn);
+        self.get_node_mut(input_id).unwrap().mark_dirty();
         Ok(())
     }
 
-    pub fn render(&self, context: &mut RenderContext) -> Result<(), &'static str> {
+    pub fn render(&self, context: &mut RenderContext) -> Result<(), NetworkError> {
         let node = self.get_rendered_node();
         if node.is_none() {
-            return Err("No rendered node.");
+            return Err(NetworkError::NoRenderedNode);
         }
-        self.render_node(context, node.unwrap().id)?;
-        Ok(())
+        let id = node.unwrap().id;
+        self.with_workers(context.threads, |workers| {
+            self.render_nodes(context, &[id], workers)
+        })
+    }
+
+    /// Render the given output port of any node and return its values. The values are shared
+    /// with the context's cache, not copied.
+    pub fn render_output(
+        &self,
+        context: &mut RenderContext,
+        id: NodeId,
+        output_port: PortIndex,
+    ) -> Result<Arc<PortSlice>, NetworkError> {
+        let mut slices = self.render_outputs(context, &[(id, output_port)])?;
+        Ok(slices.pop().unwrap())
+    }
+
+    /// Render several output ports in one pass and return their values in the same order.
+    /// Nodes that several targets depend on are rendered only once.
+    /// If a target fails, the error of the first failing target is returned.
+    pub fn render_outputs(
+        &self,
+        context: &mut RenderContext,
+        targets: &[(NodeId, PortIndex)],
+    ) -> Result<Vec<Arc<PortSlice>>, NetworkError> {
+        self.with_workers(context.threads, |workers| {
+            self.render_outputs_with(context, targets, workers)
+        })
     }
 
-    fn render_node(&self, context: &mut RenderContext, id: NodeId) -> Result<(), &'static str> {
-        let inputs = {
-            let node = self.get_node(id);
-            // FIXME: check if dirty
-            if node.is_none() {
-                return Err("Could not find node.");
+    fn render_outputs_with(
+        &self,
+        context: &mut RenderContext,
+        targets: &[(NodeId, PortIndex)],
+        workers: Option<&Workers>,
+    ) -> Result<Vec<Arc<PortSlice>>, NetworkError> {
+        for &(id, output_port) in targets {
+            let node = self.get_node(id).ok_or(NetworkError::NodeNotFound(id))?;
+            if output_port >= node.outputs.len() {
+                return Err(NetworkError::OutputPortNotFound(id, output_port));
+            }
+        }
+        let ids: Vec<NodeId> = targets.iter().map(|(id, _)| *id).collect();
+        self.render_nodes(context, &ids, workers)?;
+        Ok(targets
+            .iter()
+            .map(|&(id, output_port)| {
+                context
+                    .outputs
+                    .get(&(id, output_port))
+                    .map(Arc::clone)
+                    .unwrap_or_else(|| {
+                        let kind = self.get_output_port(id, output_port).unwrap().kind;
+                        Arc::new(PortSlice::new_empty(kind))
+                    })
+            })
+            .collect())
+    }
+
+    /// Render the given nodes and everything upstream of them, on the worker threads if given.
+    fn render_nodes(
+        &self,
+        context: &mut RenderContext,
+        ids: &[NodeId],
+        workers: Option<&Workers>,
+    ) -> Result<(), NetworkError> {
+        let order = self.render_order_of(ids)?;
+        context.begin_render();
+        match workers {
+            Some(workers) => self.render_parallel(workers, context, &order),
+            None => {
+                for id in order {
+                    self.render_node(context, id);
+                }
+            }
+        }
+        for id in ids {
+            match context.get_error(*id) {
+                Some(NetworkError::UpstreamFailed(_, failed_id)) => {
+                    return Err(context.get_error(*failed_id).unwrap().clone());
+                }
+                Some(error) => return Err(error.clone()),
+                None => {}
             }
-            &node.unwrap().inputs
-        };
-        for port_index in 0..inputs.len() {
-            self.render_input_port(context, id, port_index)?;
         }
-        self.get_node(id).unwrap().render(context);
         Ok(())
     }
 
-    fn render_input_port(
+    /// Render the rendered node once for every frame in the range, at the frame rate of the
+    /// context, and return the values of its first output for each frame. Nodes that don't
+    /// depend on time are only rendered once; later frames reuse their cached outputs.
+    /// With several threads, the same worker threads render all frames.
+    pub fn render_frames(
         &self,
         context: &mut RenderContext,
-        node_id: NodeId,
-        input_port: PortIndex,
-    ) -> Result<(), &'static str> {
-        let conn = self.get_connection_with_input(node_id, input_port);
-        if conn.is_some() {
-            let conn = conn.unwrap();
-            self.render_node(context, conn.output_id)?;
-            context.clone_output_to_input(
-                conn.output_id,
-                conn.output_port,
-                conn.input_id,
-                conn.input_port,
-            );
-        // let values = {
-        //     let output_port = self
-        //         .get_output_port(conn.output_id, &conn.output_port)
-        //         .unwrap();
-        //     output_port.values.clone()
-        // };
-
-        // FIXME: Save this in RenderContext.
-        //let mut input_port = self.get_input_port_mut(node_id, port_name).unwrap();
-        //input_port.values = values;
-        } else {
-            // The port value doesn't need to be updated.
+        frames: Range<i32>,
+    ) -> Vec<Result<Arc<PortSlice>, NetworkError>> {
+        let target = [(self.rendered_id, 0)];
+        self.with_workers(context.threads, |workers| {
+            frames
+                .map(|frame| {
+                    context.frame = frame;
+                    let mut slices = self.render_outputs_with(context, &target, workers)?;
+                    Ok(slices.pop().unwrap())
+                })
+                .collect()
+        })
+    }
+
+    /// Returns the given node and all nodes upstream of it, ordered so that each node comes
+    /// after the nodes it depends on. Every node appears exactly once.
+    ///
+    /// The graph is walked with an explicit stack, so long chains don't overflow the call stack.
+    pub fn render_order(&self, id: NodeId) -> Result<Vec<NodeId>, NetworkError> {
+        self.render_order_of(&[id])
+    }
+
+    /// Like `render_order`, for several nodes at once. Nodes that are upstream of more than one
+    /// of them still appear exactly once.
+    pub fn render_order_of(&self, ids: &[NodeId]) -> Result<Vec<NodeId>, NetworkError> {
+        let mut order = Vec::new();
+        let mut visiting = HashSet::new();
+        let mut done = HashSet::new();
+        // Each entry is a node and the next input port to follow.
+        let mut stack: Vec<(NodeId, PortIndex)> = ids.iter().rev().map(|id| (*id, 0)).collect();
+        while let Some((node_id, port_index)) = stack.pop() {
+            if port_index == 0 && done.contains(&node_id) {
+                continue;
+            }
+            let node = match self.get_node(node_id) {
+                Some(node) => node,
+                None => return Err(NetworkError::NodeNotFound(node_id)),
+            };
+            if port_index == 0 {
+                visiting.insert(node_id);
+            }
+            if port_index < node.inputs.len() {
+                stack.push((node_id, port_index + 1));
+                if let Some(conn) = self.get_connection_with_input(node_id, port_index) {
+                    if visiting.contains(&conn.output_id) {
+                        return Err(NetworkError::Cycle(conn.output_id));
+                    }
+                    if !done.contains(&conn.output_id) {
+                        stack.push((conn.output_id, 0));
+                    }
+                }
+            } else {
+                visiting.remove(&node_id);
+                done.insert(node_id);
+                order.push(node_id);
+            }
+        }
+        Ok(order)
+    }
+
+    /// Render a single node. Its upstream nodes should already have been rendered.
+    /// Errors are recorded in the context, and nodes downstream of a failed node are skipped.
+    fn render_node(&self, context: &mut RenderContext, id: NodeId) {
+        let node = self.get_node(id).unwrap();
+        if !self.prepare_node(context, node) {
+            return;
+        }
+        let start = context.stats.as_ref().map(|_| Instant::now());
+        let result = self.render_function(context, node);
+        let time = start.map(|start| start.elapsed());
+        self.finish_node(context, node, result, time);
+    }
+
+    /// Check whether the node needs to be rendered, and if so, pass the outputs of its upstream
+    /// nodes to its inputs. A node downstream of a failed node gets an error instead, as does a
+    /// node whose inputs can't be converted.
+    pub(crate) fn prepare_node(&self, context: &mut RenderContext, node: &Node) -> bool {
+        if let Some(failed_id) = context.failed_upstream(node) {
+            context.set_error(node.id, NetworkError::UpstreamFailed(node.id, failed_id));
+            return false;
+        }
+        if !context.is_dirty(node) {
+            return false;
+        }
+        for port_index in 0..node.inputs.len() {
+            if let Some(conn) = self.get_connection_with_input(node.id, port_index) {
+                let result = context.clone_output_to_input(
+                    conn.output_id,
+                    conn.output_port,
+                    conn.input_id,
+                    conn.input_port,
+                );
+                if let Err(error) = result {
+                    context.set_error(node.id, error);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Record the result of rendering the node, and the time it took if profiling is enabled.
+    pub(crate) fn finish_node(
+        &self,
+        context: &mut RenderContext,
+        node: &Node,
+        result: Result<(), NetworkError>,
+        time: Option<Duration>,
+    ) {
+        if let Some(time) = time {
+            context.record_stats(node, time);
+        }
+        match result {
+            Ok(()) => context.mark_rendered(node),
+            Err(error) => context.set_error(node.id, error),
+        }
+    }
+
+    /// Call the node's function. If a value or list input receives a nested list, the function
+    /// is called once for every sublist and the outputs are collected in nested lists.
+    /// Sublists of different inputs are combined using the node's list matching;
+    /// flat inputs are passed to every call unchanged.
+    pub(crate) fn render_function(
+        &self,
+        context: &mut RenderContext,
+        node: &Node,
+    ) -> Result<(), NetworkError> {
+        let nested_ports: Vec<PortIndex> = node
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| {
+                p.range != PortRange::Tree && context.get_input_slice(node.id, *i).is_nested()
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if nested_ports.is_empty() {
+            return node.render(context);
+        }
+        let nested_inputs: Vec<Arc<PortSlice>> = nested_ports
+            .iter()
+            .map(|i| context.get_input_shared(node.id, *i))
+            .collect();
+        let sizes = nested_inputs.iter().map(|s| s.size()).collect();
+        let list_match = ListMatch::new(node.list_matching, sizes);
+        let mut outputs: Vec<Vec<PortSlice>> = vec![Vec::new(); node.outputs.len()];
+        for i in 0..list_match.len() {
+            for (j, port_index) in nested_ports.iter().enumerate() {
+                let sublist = nested_inputs[j].sublist(list_match.index(i, j));
+                context
+                    .inputs
+                    .insert((node.id, *port_index), Arc::new(sublist));
+            }
+            self.render_function(context, node)?;
+            for (port_index, output) in outputs.iter_mut().enumerate() {
+                let slice = match context.outputs.remove(&(node.id, port_index)) {
+                    Some(slice) => {
+                        Arc::try_unwrap(slice).unwrap_or_else(|shared| (*shared).clone())
+                    }
+                    None => PortSlice::new_empty(node.outputs[port_index].kind),
+                };
+                output.push(slice);
+            }
+        }
+        for (j, port_index) in nested_ports.iter().enumerate() {
+            context
+                .inputs
+                .insert((node.id, *port_index), nested_inputs[j].clone());
+        }
+        for (port_index, lists) in outputs.into_iter().enumerate() {
+            // Generic outputs have the kind of the values that were rendered.
+            let kind = match lists.first() {
+                Some(list) if node.outputs[port_index].generic => list.kind(),
+                _ => node.outputs[port_index].kind,
+            };
+            context.set_output_slice(node.id, port_index, PortSlice::new_list(kind, lists));
         }
         Ok(())
     }
 
-    pub fn delete_node(&mut self, id: NodeId) {
-        self.nodes.retain(|n| n.id != id);
+    /// Remove the connection to the given input. Returns the removed connection.
+    pub fn disconnect(&mut self, input_id: NodeId, input_port: PortIndex) -> Option<Connection> {
+        let index = self
+            .connections
+            .iter()
+            .position(|c| c.input_id == input_id && c.input_port == input_port)?;
+        let conn = self.connections.remove(index);
+        if let Some(node) = self.get_node_mut(input_id) {
+            node.mark_dirty();
+        }
+        Some(conn)
+    }
+
+    /// Remove the node together with its connections and published ports.
+    /// Nodes that were connected to its outputs are marked dirty. Returns the removed node.
+    pub fn delete_node(&mut self, id: NodeId) -> Option<Node> {
+        let index = self.nodes.iter().position(|n| n.id == id)?;
+        let node = self.nodes.remove(index);
+        let downstream: Vec<NodeId> = self
+            .connections
+            .iter()
+            .filter(|c| c.output_id == id)
+            .map(|c| c.input_id)
+            .collect();
+        for input_id in downstream {
+            if let Some(node) = self.get_node_mut(input_id) {
+                node.mark_dirty();
+            }
+        }
+        self.connections
+            .retain(|c| c.output_id != id && c.input_id != id);
+        self.published_inputs.retain(|p| p.node_id != id);
         if self.rendered_id == id {
             self.rendered_id = 0
         }
-        // FIXME: also delete the connections
+        Some(node)
+    }
+
+    /// Copy the given nodes with fresh ids and unique names, one unit to the right and below
+    /// the originals. Connections between the copied nodes are copied as well, and inputs
+    /// connected to other nodes stay connected. Returns the ids of the copies, in order.
+    ///
+    /// Nodes are recreated through the repository, so their types need to be registered.
+    pub fn duplicate_nodes(
+        &mut self,
+        ids: &[NodeId],
+        repository: &FunctionRepository,
+    ) -> Result<Vec<NodeId>, NetworkError> {
+        let mut copies = Vec::with_capacity(ids.len());
+        let mut new_ids = HashMap::new();
+        for (new_id, &id) in (self.next_node_id()..).zip(ids) {
+            let node = self.get_node(id).ok_or(NetworkError::NodeNotFound(id))?;
+            let mut copy = copy_node(node, new_id, repository)
+                .ok_or_else(|| NetworkError::UnknownNodeType(id, node.type_name.clone()))?;
+            copy.x += 1;
+            copy.y += 1;
+            new_ids.insert(id, new_id);
+            copies.push(copy);
+        }
+        for mut copy in copies {
+            copy.name = self.unique_name(&copy.name);
+            self.nodes.push(copy);
+        }
+        let connections: Vec<Connection> = self
+            .connections
+            .iter()
+            .filter_map(|c| {
+                let input_id = *new_ids.get(&c.input_id)?;
+                let output_id = *new_ids.get(&c.output_id).unwrap_or(&c.output_id);
+                Some(Connection::new(
+                    output_id,
+                    c.output_port,
+                    input_id,
+                    c.input_port,
+                ))
+            })
+            .collect();
+        self.connections.extend(connections);
+        Ok(ids.iter().map(|id| new_ids[id]).collect())
+    }
+
+    /// Names must be unique within the network.
+    pub fn rename_node(&mut self, id: NodeId, name: &str) -> Result<(), NetworkError> {
+        if let Some(other) = self.get_node_by_name(name) {
+            if other.id != id {
+                return Err(NetworkError::DuplicateName(name.to_owned()));
+            }
+        }
+        let node = self
+            .get_node_mut(id)
+            .ok_or(NetworkError::NodeNotFound(id))?;
+        node.name = name.to_owned();
+        Ok(())
+    }
+
+    /// Moving a node doesn't change its outputs, so it is not marked dirty.
+    pub fn set_position(&mut self, id: NodeId, x: i32, y: i32) -> Result<(), NetworkError> {
+        let node = self
+            .get_node_mut(id)
+            .ok_or(NetworkError::NodeNotFound(id))?;
+        node.x = x;
+        node.y = y;
+        Ok(())
+    }
+
+    pub fn set_rendered_node(&mut self, id: NodeId) -> Result<(), NetworkError> {
+        if self.get_node(id).is_none() {
+            return Err(NetworkError::NodeNotFound(id));
+        }
+        self.rendered_id = id;
+        Ok(())
+    }
+
+    /// An id that is not used by any node. Ids start at 1.
+    pub fn next_node_id(&self) -> NodeId {
+        self.nodes.iter().map(|n| n.id).max().unwrap_or(0) + 1
+    }
+
+    /// The given name if no node has it yet, or else the name with the lowest number that is
+    /// not taken. Numbers at the end of the name are replaced, so "add1" becomes "add2".
+    pub fn unique_name(&self, name: &str) -> String {
+        if self.get_node_by_name(name).is_none() {
+            return name.to_owned();
+        }
+        let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        (1..)
+            .map(|i| format!("{}{}", base, i))
+            .find(|n| self.get_node_by_name(n).is_none())
+            .unwrap()
     }
 
     pub fn get_rendered_node(&self) -> Option<&Node> {
@@ -118,6 +516,10 @@ impl Network {
         self.nodes.iter_mut().find(|n| n.id == id)
     }
 
+    pub fn get_node_by_name(&self, name: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
     pub fn get_input_port(&self, id: NodeId, input_port: PortIndex) -> Option<&Port> {
         let node = self.get_node(id)?;
         node.get_input(input_port)
@@ -146,12 +548,127 @@ impl Network {
 
 #[cfg(test)]
 mod test {
-    // use super::*;
+    use super::*;
+    use crate::{new_node, FunctionRepository, PortKind};
+
+    #[test]
+    fn connect_errors() {
+        let repo = FunctionRepository::with_builtins();
+        let mut net = Network::new();
+        net.nodes.push(new_node(&repo, 1, "Value", 0, 0).unwrap());
+        net.nodes.push(new_node(&repo, 2, "Add", 0, 1).unwrap());
+        assert_eq!(net.connect(3, 0, 2, 0), Err(NetworkError::NodeNotFound(3)));
+        assert_eq!(net.connect(1, 0, 3, 0), Err(NetworkError::NodeNotFound(3)));
+        assert_eq!(
+            net.connect(1, 1, 2, 0),
+            Err(NetworkError::OutputPortNotFound(1, 1))
+        );
+        assert_eq!(
+            net.connect(1, 0, 2, 2),
+            Err(NetworkError::InputPortNotFound(2, 2))
+        );
+        assert!(net.connect(1, 0, 2, 1).is_ok());
+
+        let mut shape = Node::new(3, "shape1", 0, 2);
+        shape.add_geometry_output_port("shape");
+        net.nodes.push(shape);
+        assert_eq!(
+            net.connect(3, 0, 2, 0),
+            Err(NetworkError::IncompatibleKinds(
+                PortKind::Geometry,
+                PortKind::Float
+            ))
+        );
+        net.nodes.push(new_node(&repo, 4, "Group", 0, 3).unwrap());
+        assert!(net.connect(3, 0, 4, 0).is_ok());
+    }
+
+    /// Value (1) -> Add (2) <- Value (3), with Add rendered.
+    fn add_network(repo: &FunctionRepository) -> Network {
+        let mut net = Network::new();
+        net.nodes.push(new_node(repo, 1, "Value", 0, 0).unwrap());
+        net.nodes.push(new_node(repo, 2, "Add", 0, 1).unwrap());
+        net.nodes.push(new_node(repo, 3, "Value", 1, 0).unwrap());
+        net.connect(1, 0, 2, 0).unwrap();
+        net.connect(3, 0, 2, 1).unwrap();
+        net.rendered_id = 2;
+        net
+    }
 
     #[test]
     fn delete_node() {
-        //let mut net = Network::new();
-        //net.create_node("")
-        // pub fn new_node(id: NodeId, type_name: &str, x: i32, y: i32) -> Option<Box<Node>> {
+        let repo = FunctionRepository::with_builtins();
+        let mut net = add_network(&repo);
+        net.publish_input("v", 1, 0).unwrap();
+        let revision = net.get_node(2).unwrap().revision();
+        let deleted = net.delete_node(1).unwrap();
+        assert_eq!(deleted.id, 1);
+        assert_eq!(net.nodes.len(), 2);
+        assert_eq!(net.connections, vec![Connection::new(3, 0, 2, 1)]);
+        assert!(net.published_inputs.is_empty());
+        assert_ne!(net.get_node(2).unwrap().revision(), revision);
+        assert!(net.delete_node(1).is_none());
+
+        net.delete_node(2);
+        assert!(net.connections.is_empty());
+        assert_eq!(net.rendered_id, 0);
+    }
+
+    #[test]
+    fn disconnect() {
+        let repo = FunctionRepository::with_builtins();
+        let mut net = add_network(&repo);
+        assert_eq!(net.disconnect(2, 0), Some(Connection::new(1, 0, 2, 0)));
+        assert_eq!(net.disconnect(2, 0), None);
+        assert_eq!(net.connections.len(), 1);
+
+        // Connecting to an input that is already connected replaces the connection.
+        net.connect(1, 0, 2, 1).unwrap();
+        assert_eq!(net.connections, vec![Connection::new(1, 0, 2, 1)]);
+    }
+
+    #[test]
+    fn duplicate_nodes() {
+        let repo = FunctionRepository::with_builtins();
+        let mut net = add_network(&repo);
+        net.get_node_mut(1).unwrap().name = "value1".to_owned();
+        net.get_node_mut(2).unwrap().set_float("b", 0, 5.0);
+        let ids = net.duplicate_nodes(&[1, 2], &repo).unwrap();
+        assert_eq!(ids, vec![4, 5]);
+        let copy = net.get_node(5).unwrap();
+        assert_eq!((copy.x, copy.y), (1, 2));
+        assert_eq!(copy.get_input(1).unwrap().get_float(0), 5.0);
+        assert_eq!(net.get_node(4).unwrap().name, "value2");
+        assert_eq!(net.get_node(5).unwrap().name, "Add1");
+        // The copied Add is connected to the copied Value and the original second Value.
+        assert!(net.connections.contains(&Connection::new(4, 0, 5, 0)));
+        assert!(net.connections.contains(&Connection::new(3, 0, 5, 1)));
+        assert_eq!(net.connections.len(), 4);
+        assert_eq!(
+            net.duplicate_nodes(&[9], &repo),
+            Err(NetworkError::NodeNotFound(9))
+        );
+    }
+
+    #[test]
+    fn rename_and_move() {
+        let repo = FunctionRepository::with_builtins();
+        let mut net = add_network(&repo);
+        net.rename_node(1, "a").unwrap();
+        assert_eq!(net.get_node_by_name("a").unwrap().id, 1);
+        assert_eq!(
+            net.rename_node(3, "a"),
+            Err(NetworkError::DuplicateName("a".to_owned()))
+        );
+        assert!(net.rename_node(1, "a").is_ok());
+        net.set_position(3, 4, 5).unwrap();
+        assert_eq!(net.get_node(3).unwrap().x, 4);
+        assert_eq!(
+            net.set_position(9, 0, 0),
+            Err(NetworkError::NodeNotFound(9))
+        );
+        net.set_rendered_node(3).unwrap();
+        assert_eq!(net.rendered_id, 3);
+        assert_eq!(net.set_rendered_node(9), Err(NetworkError::NodeNotFound(9)));
     }
 }
@@ -1,13 +1,28 @@
-use crate::{Function, NodeId, NullFunction, Port, PortDirection, PortIndex, RenderContext};
+use crate::{
+    Color, Function, Geometry, ListMatching, NetworkError, NodeId, NullFunction, Point, Port,
+    PortDirection, PortIndex, PortKind, RenderContext,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a revision number that has never been handed out before.
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
 
 pub struct Node {
     pub id: NodeId,
     pub name: String,
-    pub function: Box<Function>,
+    /// The name the node's function is registered under in the `FunctionRepository`.
+    pub type_name: String,
+    pub function: Box<dyn Function>,
     pub x: i32,
     pub y: i32,
     pub inputs: Vec<Port>,
     pub outputs: Vec<Port>,
+    pub list_matching: ListMatching,
+    pub(crate) revision: u64,
 }
 
 impl Node {
@@ -15,14 +30,34 @@ impl Node {
         Node {
             id,
             name: name.to_owned(),
+            type_name: name.to_owned(),
             function: Box::new(NullFunction {}),
             x,
             y,
             inputs: Vec::new(),
             outputs: Vec::new(),
+            list_matching: ListMatching::default(),
+            revision: next_revision(),
         }
     }
 
+    /// The revision changes every time the node is marked dirty.
+    /// A render cache compares it against the revision it last rendered.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Mark the node as dirty so the next render recomputes it and everything downstream.
+    /// Call this after changing the ports directly instead of through the setters.
+    pub fn mark_dirty(&mut self) {
+        self.revision = next_revision();
+    }
+
+    pub fn set_list_matching(&mut self, list_matching: ListMatching) {
+        self.list_matching = list_matching;
+        self.mark_dirty();
+    }
+
     pub fn add_int_input_port(&mut self, name: &str, values: Vec<i32>) {
         self.inputs
             .push(Port::new_int_port(name, values, PortDirection::In));
@@ -38,6 +73,47 @@ impl Node {
             .push(Port::new_string_port(name, values, PortDirection::In));
     }
 
+    pub fn add_bool_input_port(&mut self, name: &str, values: Vec<bool>) {
+        self.inputs
+            .push(Port::new_bool_port(name, values, PortDirection::In));
+    }
+
+    pub fn add_point_input_port(&mut self, name: &str, values: Vec<Point>) {
+        self.inputs
+            .push(Port::new_point_port(name, values, PortDirection::In));
+    }
+
+    pub fn add_color_input_port(&mut self, name: &str, values: Vec<Color>) {
+        self.inputs
+            .push(Port::new_color_port(name, values, PortDirection::In));
+    }
+
+    pub fn add_geometry_input_port(&mut self, name: &str, values: Vec<Geometry>) {
+        self.inputs
+            .push(Port::new_geometry_port(name, values, PortDirection::In));
+    }
+
+    pub fn add_list_input_port(&mut self, name: &str, kind: PortKind) {
+        self.inputs.push(Port::new_list_input(name, kind));
+    }
+
+    pub fn add_tree_input_port(&mut self, name: &str, kind: PortKind) {
+        self.inputs.push(Port::new_tree_input(name, kind));
+    }
+
+    /// An input that accepts values of any kind, for nodes that don't look at the values.
+    pub fn add_generic_input_port(&mut self, name: &str) {
+        self.inputs.push(Port::new_generic_input(name));
+    }
+
+    pub fn add_generic_list_input_port(&mut self, name: &str) {
+        self.inputs.push(Port::new_generic_list_input(name));
+    }
+
+    pub fn add_generic_tree_input_port(&mut self, name: &str) {
+        self.inputs.push(Port::new_generic_tree_input(name));
+    }
+
     pub fn add_int_output_port(&mut self, name: &str) {
         self.outputs
             .push(Port::new_int_port(name, vec![], PortDirection::Out));
@@ -49,12 +125,41 @@ impl Node {
     }
 
     pub fn add_string_output_port(&mut self, name: &str) {
-        self.inputs
+        self.outputs
             .push(Port::new_string_port(name, vec![], PortDirection::Out));
     }
 
-    pub fn render(&self, ctx: &mut RenderContext) {
-        self.function.render(&self, ctx)
+    pub fn add_bool_output_port(&mut self, name: &str) {
+        self.outputs
+            .push(Port::new_bool_port(name, vec![], PortDirection::Out));
+    }
+
+    pub fn add_point_output_port(&mut self, name: &str) {
+        self.outputs
+            .push(Port::new_point_port(name, vec![], PortDirection::Out));
+    }
+
+    pub fn add_color_output_port(&mut self, name: &str) {
+        self.outputs
+            .push(Port::new_color_port(name, vec![], PortDirection::Out));
+    }
+
+    pub fn add_geometry_output_port(&mut self, name: &str) {
+        self.outputs
+            .push(Port::new_geometry_port(name, vec![], PortDirection::Out));
+    }
+
+    /// An output that has the kind of the values the node receives on its generic inputs.
+    pub fn add_generic_output_port(&mut self, name: &str) {
+        self.outputs.push(Port::new_generic_output(name));
+    }
+
+    pub fn render(&self, ctx: &mut RenderContext) -> Result<(), NetworkError> {
+        self.function.render(self, ctx)
+    }
+
+    pub fn is_time_dependent(&self) -> bool {
+        self.function.is_time_dependent()
     }
 
     pub fn get_input(&self, index: PortIndex) -> Option<&Port> {
@@ -84,14 +189,70 @@ impl Node {
     pub fn set_float(&mut self, name: &str, index: usize, v: f32) {
         match self.get_input_by_name_mut(name) {
             None => {}
-            Some(input) => input.set_float(index, v),
+            Some(input) => {
+                input.set_float(index, v);
+                self.mark_dirty();
+            }
         }
     }
 
     pub fn set_string(&mut self, name: &str, index: usize, v: &str) {
         match self.get_input_by_name_mut(name) {
             None => {}
-            Some(input) => input.set_string(index, v),
+            Some(input) => {
+                input.set_string(index, v);
+                self.mark_dirty();
+            }
+        }
+    }
+
+    pub fn set_int(&mut self, name: &str, index: usize, v: i32) {
+        match self.get_input_by_name_mut(name) {
+            None => {}
+            Some(input) => {
+                input.set_int(index, v);
+                self.mark_dirty();
+            }
+        }
+    }
+
+    pub fn set_bool(&mut self, name: &str, index: usize, v: bool) {
+        match self.get_input_by_name_mut(name) {
+            None => {}
+            Some(input) => {
+                input.set_bool(index, v);
+                self.mark_dirty();
+            }
+        }
+    }
+
+    pub fn set_point(&mut self, name: &str, index: usize, v: Point) {
+        match self.get_input_by_name_mut(name) {
+            None => {}
+            Some(input) => {
+                input.set_point(index, v);
+                self.mark_dirty();
+            }
+        }
+    }
+
+    pub fn set_color(&mut self, name: &str, index: usize, v: Color) {
+        match self.get_input_by_name_mut(name) {
+            None => {}
+            Some(input) => {
+                input.set_color(index, v);
+                self.mark_dirty();
+            }
+        }
+    }
+
+    pub fn set_geometry(&mut self, name: &str, index: usize, v: Geometry) {
+        match self.get_input_by_name_mut(name) {
+            None => {}
+            Some(input) => {
+                input.set_geometry(index, v);
+                self.mark_dirty();
+            }
         }
     }
 
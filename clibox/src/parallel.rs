@@ -0,0 +1,223 @@
+//! Renders nodes that don't depend on each other on several threads.
+//!
+//! The calling thread keeps the `RenderContext` and schedules the nodes: a node is handed to one
+//! of the `Workers` once all nodes upstream of it are done. The worker renders it in a context of
+//! its own, holding only the node's inputs, and sends the outputs back. The inputs and outputs
+//! are shared, not copied. Deciding which nodes to render and recording the results happens on
+//! the calling thread in the same way as in a sequential render, so the results are the same.
+
+use crate::{Network, NetworkError, Node, NodeId, PortIndex, PortSlice, RenderContext};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Values = Vec<(PortIndex, Arc<PortSlice>)>;
+
+/// A node to render, with everything from the render context it needs.
+struct Job {
+    id: NodeId,
+    inputs: Values,
+    input_values: Values,
+    frame: i32,
+    fps: f32,
+}
+
+struct JobResult {
+    id: NodeId,
+    result: Result<(), NetworkError>,
+    outputs: Values,
+    time: Duration,
+}
+
+/// Keeps track of which nodes can be rendered.
+struct Schedule {
+    /// The number of upstream nodes that are not done yet.
+    waiting: HashMap<NodeId, usize>,
+    downstream: HashMap<NodeId, Vec<NodeId>>,
+    ready: VecDeque<NodeId>,
+    remaining: usize,
+}
+
+impl Schedule {
+    fn new(network: &Network, order: &[NodeId]) -> Schedule {
+        let in_order: HashSet<NodeId> = order.iter().copied().collect();
+        let mut waiting = HashMap::new();
+        let mut downstream: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for &id in order {
+            let mut upstream: Vec<NodeId> = network
+                .connections
+                .iter()
+                .filter(|c| c.input_id == id && in_order.contains(&c.output_id))
+                .map(|c| c.output_id)
+                .collect();
+            upstream.sort_unstable();
+            upstream.dedup();
+            waiting.insert(id, upstream.len());
+            for upstream_id in upstream {
+                downstream.entry(upstream_id).or_default().push(id);
+            }
+        }
+        // Nodes without upstream nodes start in render order.
+        let ready = order
+            .iter()
+            .filter(|id| waiting[id] == 0)
+            .copied()
+            .collect();
+        Schedule {
+            waiting,
+            downstream,
+            ready,
+            remaining: order.len(),
+        }
+    }
+
+    fn done(&mut self, id: NodeId) {
+        self.remaining -= 1;
+        for downstream_id in self.downstream.remove(&id).unwrap_or_default() {
+            let waiting = self.waiting.get_mut(&downstream_id).unwrap();
+            *waiting -= 1;
+            if *waiting == 0 {
+                self.ready.push_back(downstream_id);
+            }
+        }
+    }
+}
+
+/// Worker threads for parallel renders. They are reused by every render done while they exist,
+/// and stop when this is dropped.
+pub(crate) struct Workers {
+    jobs: mpsc::Sender<Job>,
+    results: mpsc::Receiver<thread::Result<JobResult>>,
+}
+
+impl Workers {
+    fn new<'scope, 'env>(
+        scope: &'scope thread::Scope<'scope, 'env>,
+        network: &'env Network,
+        threads: usize,
+    ) -> Workers {
+        let (job_sender, job_receiver) = mpsc::channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let (result_sender, result_receiver) = mpsc::channel();
+        for _ in 0..threads {
+            let job_receiver = Arc::clone(&job_receiver);
+            let result_sender = result_sender.clone();
+            scope.spawn(move || loop {
+                let job = match job_receiver.lock().unwrap().recv() {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                // A panic is sent back as a result, so the calling thread can raise it again
+                // instead of waiting for a result that never comes.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| network.render_job(job)));
+                if result_sender.send(result).is_err() {
+                    break;
+                }
+            });
+        }
+        Workers {
+            jobs: job_sender,
+            results: result_receiver,
+        }
+    }
+
+    /// Render the nodes, which should be in render order. If a function panics, the panic is
+    /// raised again on the calling thread, as in a sequential render.
+    fn render(&self, network: &Network, context: &mut RenderContext, order: &[NodeId]) {
+        let mut schedule = Schedule::new(network, order);
+        while schedule.remaining > 0 {
+            while let Some(id) = schedule.ready.pop_front() {
+                let node = network.get_node(id).unwrap();
+                if network.prepare_node(context, node) {
+                    self.jobs.send(new_job(context, node)).unwrap();
+                } else {
+                    schedule.done(id);
+                }
+            }
+            if schedule.remaining == 0 {
+                break;
+            }
+            let job_result = match self.results.recv().unwrap() {
+                Ok(job_result) => job_result,
+                Err(payload) => panic::resume_unwind(payload),
+            };
+            let node = network.get_node(job_result.id).unwrap();
+            let time = context.stats.as_ref().map(|_| job_result.time);
+            for (port_index, slice) in job_result.outputs {
+                context.set_output_shared(node.id, port_index, slice);
+            }
+            network.finish_node(context, node, job_result.result, time);
+            schedule.done(node.id);
+        }
+    }
+}
+
+impl Network {
+    /// Call `f` with worker threads if more than one thread is requested. The threads are
+    /// stopped when `f` returns, after the renders it did are complete.
+    pub(crate) fn with_workers<R>(
+        &self,
+        threads: usize,
+        f: impl FnOnce(Option<&Workers>) -> R,
+    ) -> R {
+        if threads > 1 {
+            thread::scope(|scope| f(Some(&Workers::new(scope, self, threads))))
+        } else {
+            f(None)
+        }
+    }
+
+    /// Render the nodes, which should be in render order, on the worker threads.
+    pub(crate) fn render_parallel(
+        &self,
+        workers: &Workers,
+        context: &mut RenderContext,
+        order: &[NodeId],
+    ) {
+        workers.render(self, context, order);
+    }
+
+    /// Render a single node on a worker thread.
+    fn render_job(&self, job: Job) -> JobResult {
+        let node = self.get_node(job.id).unwrap();
+        let mut context = RenderContext::new(self);
+        context.frame = job.frame;
+        context.fps = job.fps;
+        for (port_index, slice) in job.inputs {
+            context.inputs.insert((job.id, port_index), slice);
+        }
+        for (port_index, slice) in job.input_values {
+            context.input_values.insert((job.id, port_index), slice);
+        }
+        let start = Instant::now();
+        let result = self.render_function(&mut context, node);
+        let time = start.elapsed();
+        let outputs = (0..node.outputs.len())
+            .filter_map(|i| context.outputs.remove(&(node.id, i)).map(|s| (i, s)))
+            .collect();
+        JobResult {
+            id: node.id,
+            result,
+            outputs,
+            time,
+        }
+    }
+}
+
+fn new_job(context: &RenderContext, node: &Node) -> Job {
+    let values = |map: &HashMap<(NodeId, PortIndex), Arc<PortSlice>>| {
+        (0..node.inputs.len())
+            .filter_map(|i| map.get(&(node.id, i)).map(|s| (i, Arc::clone(s))))
+            .collect()
+    };
+    Job {
+        id: node.id,
+        inputs: values(&context.inputs),
+        input_values: values(&context.input_values),
+        frame: context.frame,
+        fps: context.fps,
+    }
+}
@@ -1,20 +1,68 @@
+use crate::{Color, Geometry, Path, Point};
+use serde::{Deserialize, Serialize};
+
 pub enum PortDirection {
     In,
     Out,
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Whether a function receives the values of an input port one by one, or the whole list at once.
+/// This mirrors the `range` attribute of NodeBox ports.
+///
+/// Value and list ports work on flat lists: when a nested list comes in, the node is rendered
+/// once for every sublist. Tree ports receive nested lists unchanged.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum PortRange {
+    #[default]
+    Value,
+    List,
+    Tree,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 pub enum PortKind {
     Int,
     Float,
     String,
+    Bool,
+    Point,
+    Color,
+    Geometry,
+}
+
+impl PortKind {
+    /// Whether an output of this kind can be connected to an input of the other kind.
+    ///
+    /// Ints, floats and bools convert into each other, everything converts to a string and
+    /// strings are parsed into all kinds except geometry. Numbers also convert to points and
+    /// shades of gray, and a point converts to a geometry containing that point.
+    pub fn can_convert_to(self, kind: PortKind) -> bool {
+        use PortKind::*;
+        match (self, kind) {
+            (a, b) if a == b => true,
+            (Int | Float | Bool, Int | Float | Bool) => true,
+            (_, String) => true,
+            (String, Geometry) => false,
+            (String, _) => true,
+            (Int | Float | Bool, Color) => true,
+            (Int | Float, Point) => true,
+            (Point, Geometry) => true,
+            _ => false,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum PortSlice {
     Int(Vec<i32>),
     Float(Vec<f32>),
     String(Vec<String>),
+    Bool(Vec<bool>),
+    Point(Vec<Point>),
+    Color(Vec<Color>),
+    Geometry(Vec<Geometry>),
+    /// A list of lists, all holding values of the given kind.
+    List(PortKind, Vec<PortSlice>),
 }
 
 impl PortSlice {
@@ -23,15 +71,17 @@ impl PortSlice {
             PortKind::Int => PortSlice::Int(Vec::new()),
             PortKind::Float => PortSlice::Float(Vec::new()),
             PortKind::String => PortSlice::String(Vec::new()),
+            PortKind::Bool => PortSlice::Bool(Vec::new()),
+            PortKind::Point => PortSlice::Point(Vec::new()),
+            PortKind::Color => PortSlice::Color(Vec::new()),
+            PortKind::Geometry => PortSlice::Geometry(Vec::new()),
         }
     }
 
     pub fn new_single(kind: PortKind) -> PortSlice {
-        match kind {
-            PortKind::Int => PortSlice::Int(vec![0]),
-            PortKind::Float => PortSlice::Float(vec![0.0]),
-            PortKind::String => PortSlice::String(vec!["".to_owned()]),
-        }
+        let mut slice = PortSlice::new_empty(kind);
+        slice.ensure_size(1);
+        slice
     }
 
     pub fn new_int(values: Vec<i32>) -> PortSlice {
@@ -47,36 +97,397 @@ impl PortSlice {
         PortSlice::String(values)
     }
 
+    pub fn new_bool(values: Vec<bool>) -> PortSlice {
+        PortSlice::Bool(values)
+    }
+
+    pub fn new_point(values: Vec<Point>) -> PortSlice {
+        PortSlice::Point(values)
+    }
+
+    pub fn new_color(values: Vec<Color>) -> PortSlice {
+        PortSlice::Color(values)
+    }
+
+    pub fn new_geometry(values: Vec<Geometry>) -> PortSlice {
+        PortSlice::Geometry(values)
+    }
+
+    pub fn new_list(kind: PortKind, lists: Vec<PortSlice>) -> PortSlice {
+        PortSlice::List(kind, lists)
+    }
+
+    /// Parse values of the given kind separated by semicolons, e.g. "1;2;3" or "#ff0000;#0000ff".
+    /// Returns None if one of the values can't be parsed. Geometry can't be parsed.
+    pub fn parse(kind: PortKind, text: &str) -> Option<PortSlice> {
+        let parts = text.split(';');
+        let slice = match kind {
+            PortKind::Int => PortSlice::Int(
+                parts
+                    .map(|p| p.trim().parse().ok())
+                    .collect::<Option<_>>()?,
+            ),
+            PortKind::Float => PortSlice::Float(
+                parts
+                    .map(|p| p.trim().parse().ok())
+                    .collect::<Option<_>>()?,
+            ),
+            PortKind::String => PortSlice::String(parts.map(|p| p.to_owned()).collect()),
+            PortKind::Bool => PortSlice::Bool(
+                parts
+                    .map(|p| match p.trim() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            PortKind::Point => PortSlice::Point(parts.map(Point::parse).collect::<Option<_>>()?),
+            PortKind::Color => PortSlice::Color(parts.map(Color::from_hex).collect::<Option<_>>()?),
+            PortKind::Geometry => return None,
+        };
+        Some(slice)
+    }
+
+    pub fn kind(&self) -> PortKind {
+        match &self {
+            PortSlice::Int(_) => PortKind::Int,
+            PortSlice::Float(_) => PortKind::Float,
+            PortSlice::String(_) => PortKind::String,
+            PortSlice::Bool(_) => PortKind::Bool,
+            PortSlice::Point(_) => PortKind::Point,
+            PortSlice::Color(_) => PortKind::Color,
+            PortSlice::Geometry(_) => PortKind::Geometry,
+            PortSlice::List(kind, _) => *kind,
+        }
+    }
+
     pub fn size(&self) -> usize {
         // If performance is a bottleneck we could transmute the enum to any kind of vector and ask the size.
         match &self {
             PortSlice::Int(vals) => vals.len(),
             PortSlice::Float(vals) => vals.len(),
             PortSlice::String(vals) => vals.len(),
+            PortSlice::Bool(vals) => vals.len(),
+            PortSlice::Point(vals) => vals.len(),
+            PortSlice::Color(vals) => vals.len(),
+            PortSlice::Geometry(vals) => vals.len(),
+            PortSlice::List(_, lists) => lists.len(),
+        }
+    }
+
+    pub fn is_nested(&self) -> bool {
+        matches!(self, PortSlice::List(_, _))
+    }
+
+    /// Whether all sublists hold values of the kind the list declares. Lists built with the
+    /// methods of `PortSlice` always do; lists read from a file may not.
+    pub fn has_consistent_kind(&self) -> bool {
+        match self {
+            PortSlice::List(kind, lists) => lists
+                .iter()
+                .all(|l| l.kind() == *kind && l.has_consistent_kind()),
+            _ => true,
         }
     }
 
+    /// A flat list has depth 1, a list of lists depth 2, and so on.
+    pub fn depth(&self) -> usize {
+        match self {
+            PortSlice::List(_, lists) => 1 + lists.iter().map(|l| l.depth()).max().unwrap_or(1),
+            _ => 1,
+        }
+    }
+
+    /// The number of values in the list and all of its sublists.
+    pub fn flat_size(&self) -> usize {
+        match self {
+            PortSlice::List(_, lists) => lists.iter().map(|l| l.flat_size()).sum(),
+            _ => self.size(),
+        }
+    }
+
+    /// The sublist at the given index, wrapping around. A flat list is its own sublist.
+    /// A nested list without sublists gives an empty list.
+    pub fn sublist(&self, index: usize) -> PortSlice {
+        match self {
+            PortSlice::List(kind, lists) if lists.is_empty() => PortSlice::new_empty(*kind),
+            PortSlice::List(_, lists) => lists[index % lists.len()].clone(),
+            _ => self.clone(),
+        }
+    }
+
+    /// All values of the list and its sublists in a single flat list.
+    pub fn flatten(&self) -> PortSlice {
+        let mut flat = PortSlice::new_empty(self.kind());
+        flat.append(self);
+        flat
+    }
+
+    /// Split the items of the list into sublists of the given size. The last sublist may be shorter.
+    pub fn group(&self, size: usize) -> PortSlice {
+        let size = size.max(1);
+        let groups = (0..self.size())
+            .step_by(size)
+            .map(|start| self.sub_slice(start, (start + size).min(self.size())))
+            .collect();
+        PortSlice::List(self.kind(), groups)
+    }
+
+    /// The items from start up to (but not including) end.
+    pub fn sub_slice(&self, start: usize, end: usize) -> PortSlice {
+        match self {
+            PortSlice::Int(vals) => PortSlice::Int(vals[start..end].to_vec()),
+            PortSlice::Float(vals) => PortSlice::Float(vals[start..end].to_vec()),
+            PortSlice::String(vals) => PortSlice::String(vals[start..end].to_vec()),
+            PortSlice::Bool(vals) => PortSlice::Bool(vals[start..end].to_vec()),
+            PortSlice::Point(vals) => PortSlice::Point(vals[start..end].to_vec()),
+            PortSlice::Color(vals) => PortSlice::Color(vals[start..end].to_vec()),
+            PortSlice::Geometry(vals) => PortSlice::Geometry(vals[start..end].to_vec()),
+            PortSlice::List(kind, lists) => PortSlice::List(*kind, lists[start..end].to_vec()),
+        }
+    }
+
+    /// The values converted to the given kind, keeping the nesting of the list.
+    pub fn convert(&self, kind: PortKind) -> PortSlice {
+        match self {
+            PortSlice::List(_, lists) => {
+                PortSlice::List(kind, lists.iter().map(|l| l.convert(kind)).collect())
+            }
+            _ if self.kind() == kind => self.clone(),
+            _ => {
+                let mut converted = PortSlice::new_empty(kind);
+                converted.append(self);
+                converted
+            }
+        }
+    }
+
+    /// Like `convert`, but strings that can't be parsed as the given kind are an error instead
+    /// of becoming a default value. The error holds the first of those strings.
+    pub fn try_convert(&self, kind: PortKind) -> Result<PortSlice, String> {
+        if self.kind() == PortKind::String && kind != PortKind::String {
+            for i in 0..self.flat_size() {
+                let value = self.get_string(i);
+                if !can_parse(kind, &value) {
+                    return Err(value);
+                }
+            }
+        }
+        Ok(self.convert(kind))
+    }
+
+    /// The items at the given indices, in that order. Indices wrap around, so the result can
+    /// only be empty if there are no indices or the list is empty.
+    pub fn select(&self, indices: &[usize]) -> PortSlice {
+        fn pick<T: Clone>(vals: &[T], indices: &[usize]) -> Vec<T> {
+            if vals.is_empty() {
+                return Vec::new();
+            }
+            indices
+                .iter()
+                .map(|i| vals[i % vals.len()].clone())
+                .collect()
+        }
+        match self {
+            PortSlice::Int(vals) => PortSlice::Int(pick(vals, indices)),
+            PortSlice::Float(vals) => PortSlice::Float(pick(vals, indices)),
+            PortSlice::String(vals) => PortSlice::String(pick(vals, indices)),
+            PortSlice::Bool(vals) => PortSlice::Bool(pick(vals, indices)),
+            PortSlice::Point(vals) => PortSlice::Point(pick(vals, indices)),
+            PortSlice::Color(vals) => PortSlice::Color(pick(vals, indices)),
+            PortSlice::Geometry(vals) => PortSlice::Geometry(pick(vals, indices)),
+            PortSlice::List(kind, lists) => PortSlice::List(*kind, pick(lists, indices)),
+        }
+    }
+
+    /// Append all values of the other list, converted to the kind of this list.
+    /// Nested lists are flattened, unless this list is nested itself: then the other list is
+    /// appended as a new sublist.
+    pub fn append(&mut self, other: &PortSlice) {
+        let n = other.flat_size();
+        match self {
+            PortSlice::Int(vals) => vals.extend((0..n).map(|i| other.get_int(i))),
+            PortSlice::Float(vals) => vals.extend((0..n).map(|i| other.get_float(i))),
+            PortSlice::String(vals) => vals.extend((0..n).map(|i| other.get_string(i))),
+            PortSlice::Bool(vals) => vals.extend((0..n).map(|i| other.get_bool(i))),
+            PortSlice::Point(vals) => vals.extend((0..n).map(|i| other.get_point(i))),
+            PortSlice::Color(vals) => vals.extend((0..n).map(|i| other.get_color(i))),
+            PortSlice::Geometry(vals) => vals.extend((0..n).map(|i| other.get_geometry(i))),
+            PortSlice::List(_, lists) => lists.push(other.clone()),
+        }
+    }
+
+    /// The flat list holding the value at the given index, and the index within that list.
+    /// For nested lists the index counts the values of all sublists, wrapping around.
+    /// Returns None if the list and its sublists hold no values.
+    fn leaf(&self, index: usize) -> Option<(&PortSlice, usize)> {
+        let flat_size = self.flat_size();
+        if flat_size == 0 {
+            return None;
+        }
+        match self {
+            PortSlice::List(_, lists) => {
+                let mut index = index % flat_size;
+                for list in lists {
+                    let size = list.flat_size();
+                    if index < size {
+                        return list.leaf(index);
+                    }
+                    index -= size;
+                }
+                unreachable!()
+            }
+            _ => Some((self, index)),
+        }
+    }
+
+    /// Like `leaf`, but indices past the end don't wrap: they fall in the last sublist.
+    fn leaf_mut(&mut self, index: usize) -> (&mut PortSlice, usize) {
+        match self {
+            PortSlice::List(kind, lists) => {
+                if lists.is_empty() {
+                    lists.push(PortSlice::new_empty(*kind));
+                }
+                let mut index = index;
+                let mut i = 0;
+                while i < lists.len() - 1 && index >= lists[i].flat_size() {
+                    index -= lists[i].flat_size();
+                    i += 1;
+                }
+                lists[i].leaf_mut(index)
+            }
+            _ => (self, index),
+        }
+    }
+
+    // The getters wrap around, so index 5 of a list of 3 values returns the value at index 2.
+    // Values are converted to the requested kind. Strings are parsed.
+    // Values that can't be converted return a default; use `try_convert` to detect those.
+    // Empty lists also return a default.
+    // Nested lists are indexed as if they were flattened.
+
     pub fn get_int(&self, index: usize) -> i32 {
-        match &self {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return 0,
+        };
+        match slice {
             PortSlice::Int(vals) => vals[index % vals.len()],
             PortSlice::Float(vals) => vals[index % vals.len()] as i32,
-            PortSlice::String(_) => 0,
+            PortSlice::String(vals) => parse_int(&vals[index % vals.len()]),
+            PortSlice::Bool(vals) => vals[index % vals.len()] as i32,
+            _ => 0,
         }
     }
 
     pub fn get_float(&self, index: usize) -> f32 {
-        match &self {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return 0.0,
+        };
+        match slice {
             PortSlice::Int(vals) => vals[index % vals.len()] as f32,
             PortSlice::Float(vals) => vals[index % vals.len()],
-            PortSlice::String(_) => 0.0,
+            PortSlice::String(vals) => vals[index % vals.len()].trim().parse().unwrap_or(0.0),
+            PortSlice::Bool(vals) => vals[index % vals.len()] as i32 as f32,
+            _ => 0.0,
         }
     }
 
     pub fn get_string(&self, index: usize) -> String {
-        match &self {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return String::new(),
+        };
+        match slice {
             PortSlice::Int(vals) => format!("{}", vals[index % vals.len()]),
             PortSlice::Float(vals) => format!("{}", vals[index % vals.len()]),
             PortSlice::String(vals) => vals[index % vals.len()].to_owned(),
+            PortSlice::Bool(vals) => format!("{}", vals[index % vals.len()]),
+            PortSlice::Point(vals) => format!("{}", vals[index % vals.len()]),
+            PortSlice::Color(vals) => format!("{}", vals[index % vals.len()]),
+            PortSlice::Geometry(vals) => format!("{}", vals[index % vals.len()]),
+            PortSlice::List(_, _) => unreachable!(),
+        }
+    }
+
+    pub fn get_bool(&self, index: usize) -> bool {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return false,
+        };
+        match slice {
+            PortSlice::Int(vals) => vals[index % vals.len()] != 0,
+            PortSlice::Float(vals) => vals[index % vals.len()] != 0.0,
+            PortSlice::String(vals) => vals[index % vals.len()].trim() == "true",
+            PortSlice::Bool(vals) => vals[index % vals.len()],
+            PortSlice::Point(vals) => vals[index % vals.len()] != Point::default(),
+            PortSlice::Color(vals) => vals[index % vals.len()].a > 0.0,
+            PortSlice::Geometry(vals) => !vals[index % vals.len()].is_empty(),
+            PortSlice::List(_, _) => unreachable!(),
+        }
+    }
+
+    /// A number v is converted to the point (v, v).
+    pub fn get_point(&self, index: usize) -> Point {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return Point::default(),
+        };
+        match slice {
+            PortSlice::Int(vals) => {
+                let v = vals[index % vals.len()] as f32;
+                Point::new(v, v)
+            }
+            PortSlice::Float(vals) => {
+                let v = vals[index % vals.len()];
+                Point::new(v, v)
+            }
+            PortSlice::String(vals) => Point::parse(&vals[index % vals.len()]).unwrap_or_default(),
+            PortSlice::Point(vals) => vals[index % vals.len()],
+            _ => Point::default(),
+        }
+    }
+
+    /// Floats between 0 and 1 and ints between 0 and 255 are converted to a shade of gray.
+    /// Strings are parsed as hex colors.
+    pub fn get_color(&self, index: usize) -> Color {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return Color::default(),
+        };
+        match slice {
+            PortSlice::Int(vals) => Color::gray(vals[index % vals.len()] as f32 / 255.0),
+            PortSlice::Float(vals) => Color::gray(vals[index % vals.len()]),
+            PortSlice::String(vals) => {
+                Color::from_hex(&vals[index % vals.len()]).unwrap_or_default()
+            }
+            PortSlice::Bool(vals) => {
+                if vals[index % vals.len()] {
+                    Color::gray(1.0)
+                } else {
+                    Color::gray(0.0)
+                }
+            }
+            PortSlice::Color(vals) => vals[index % vals.len()],
+            _ => Color::default(),
+        }
+    }
+
+    /// A point is converted to a path containing only that point.
+    pub fn get_geometry(&self, index: usize) -> Geometry {
+        let (slice, index) = match self.leaf(index) {
+            Some(leaf) => leaf,
+            None => return Geometry::default(),
+        };
+        match slice {
+            PortSlice::Point(vals) => {
+                let point = vals[index % vals.len()];
+                Geometry::new(vec![Path::new(vec![point], false)])
+            }
+            PortSlice::Geometry(vals) => vals[index % vals.len()].clone(),
+            _ => Geometry::default(),
         }
     }
 
@@ -97,36 +508,157 @@ impl PortSlice {
                     vals.resize(new_size, "".to_owned())
                 }
             }
+            PortSlice::Bool(vals) => {
+                if new_size > vals.len() {
+                    vals.resize(new_size, false)
+                }
+            }
+            PortSlice::Point(vals) => {
+                if new_size > vals.len() {
+                    vals.resize(new_size, Point::default())
+                }
+            }
+            PortSlice::Color(vals) => {
+                if new_size > vals.len() {
+                    vals.resize(new_size, Color::default())
+                }
+            }
+            PortSlice::Geometry(vals) => {
+                if new_size > vals.len() {
+                    vals.resize(new_size, Geometry::default())
+                }
+            }
+            PortSlice::List(kind, lists) => {
+                if new_size > lists.len() {
+                    lists.resize(new_size, PortSlice::new_empty(*kind))
+                }
+            }
         }
     }
 
+    // The setters grow the slice if needed and convert the value to the kind of the slice,
+    // following the same rules as the getters. Nested lists grow their last sublist.
+
     pub fn set_int(&mut self, index: usize, v: i32) {
-        self.ensure_size(index + 1);
-        match self {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
             PortSlice::Int(vals) => vals[index] = v,
             PortSlice::Float(vals) => vals[index] = v as f32,
             PortSlice::String(vals) => vals[index] = format!("{}", v),
+            PortSlice::Bool(vals) => vals[index] = v != 0,
+            PortSlice::Point(vals) => vals[index] = Point::new(v as f32, v as f32),
+            PortSlice::Color(vals) => vals[index] = Color::gray(v as f32 / 255.0),
+            PortSlice::Geometry(vals) => vals[index] = Geometry::default(),
+            PortSlice::List(_, _) => unreachable!(),
         }
     }
 
     pub fn set_float(&mut self, index: usize, v: f32) {
-        self.ensure_size(index + 1);
-        match self {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
             PortSlice::Int(vals) => vals[index] = v as i32,
             PortSlice::Float(vals) => vals[index] = v,
             PortSlice::String(vals) => vals[index] = format!("{}", v),
+            PortSlice::Bool(vals) => vals[index] = v != 0.0,
+            PortSlice::Point(vals) => vals[index] = Point::new(v, v),
+            PortSlice::Color(vals) => vals[index] = Color::gray(v),
+            PortSlice::Geometry(vals) => vals[index] = Geometry::default(),
+            PortSlice::List(_, _) => unreachable!(),
         }
     }
 
     pub fn set_string(&mut self, index: usize, v: &str) {
-        // FIXME: Add parsing here?
-        self.ensure_size(index + 1);
-        match self {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
+            PortSlice::Int(vals) => vals[index] = parse_int(v),
+            PortSlice::Float(vals) => vals[index] = v.trim().parse().unwrap_or(0.0),
+            PortSlice::String(vals) => vals[index] = v.to_owned(),
+            PortSlice::Bool(vals) => vals[index] = v.trim() == "true",
+            PortSlice::Point(vals) => vals[index] = Point::parse(v).unwrap_or_default(),
+            PortSlice::Color(vals) => vals[index] = Color::from_hex(v).unwrap_or_default(),
+            PortSlice::Geometry(vals) => vals[index] = Geometry::default(),
+            PortSlice::List(_, _) => unreachable!(),
+        }
+    }
+
+    pub fn set_bool(&mut self, index: usize, v: bool) {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
+            PortSlice::Bool(vals) => vals[index] = v,
+            slice => slice.set_int(index, v as i32),
+        }
+    }
+
+    pub fn set_point(&mut self, index: usize, v: Point) {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
             PortSlice::Int(vals) => vals[index] = 0,
             PortSlice::Float(vals) => vals[index] = 0.0,
-            PortSlice::String(vals) => vals[index] = v.to_owned(),
+            PortSlice::String(vals) => vals[index] = format!("{}", v),
+            PortSlice::Bool(vals) => vals[index] = v != Point::default(),
+            PortSlice::Point(vals) => vals[index] = v,
+            PortSlice::Color(vals) => vals[index] = Color::default(),
+            PortSlice::Geometry(vals) => {
+                vals[index] = Geometry::new(vec![Path::new(vec![v], false)])
+            }
+            PortSlice::List(_, _) => unreachable!(),
         }
     }
+
+    pub fn set_color(&mut self, index: usize, v: Color) {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
+            PortSlice::Int(vals) => vals[index] = 0,
+            PortSlice::Float(vals) => vals[index] = 0.0,
+            PortSlice::String(vals) => vals[index] = v.to_hex(),
+            PortSlice::Bool(vals) => vals[index] = v.a > 0.0,
+            PortSlice::Point(vals) => vals[index] = Point::default(),
+            PortSlice::Color(vals) => vals[index] = v,
+            PortSlice::Geometry(vals) => vals[index] = Geometry::default(),
+            PortSlice::List(_, _) => unreachable!(),
+        }
+    }
+
+    pub fn set_geometry(&mut self, index: usize, v: Geometry) {
+        let (slice, index) = self.leaf_mut(index);
+        slice.ensure_size(index + 1);
+        match slice {
+            PortSlice::Int(vals) => vals[index] = 0,
+            PortSlice::Float(vals) => vals[index] = 0.0,
+            PortSlice::String(vals) => vals[index] = format!("{}", v),
+            PortSlice::Bool(vals) => vals[index] = !v.is_empty(),
+            PortSlice::Point(vals) => vals[index] = Point::default(),
+            PortSlice::Color(vals) => vals[index] = Color::default(),
+            PortSlice::Geometry(vals) => vals[index] = v,
+            PortSlice::List(_, _) => unreachable!(),
+        }
+    }
+}
+
+/// Whether the getter for the kind parses the string instead of returning a default.
+fn can_parse(kind: PortKind, s: &str) -> bool {
+    match kind {
+        PortKind::Int | PortKind::Float => s.trim().parse::<f32>().is_ok(),
+        PortKind::String => true,
+        PortKind::Bool => matches!(s.trim(), "true" | "false"),
+        PortKind::Point => Point::parse(s).is_some(),
+        PortKind::Color => Color::from_hex(s).is_some(),
+        PortKind::Geometry => false,
+    }
+}
+
+/// Parse an int, truncating floats like "2.5". Returns 0 if the string is not a number.
+fn parse_int(s: &str) -> i32 {
+    let s = s.trim();
+    s.parse::<i32>()
+        .or_else(|_| s.parse::<f32>().map(|v| v as i32))
+        .unwrap_or(0)
 }
 
 pub struct Port {
@@ -134,6 +666,10 @@ pub struct Port {
     pub kind: PortKind,
     pub slice: PortSlice,
     pub direction: PortDirection,
+    pub range: PortRange,
+    /// Generic ports accept values of any kind and pass them on unconverted, e.g. for nodes that
+    /// reorder lists. `kind` is then only the kind of the default value.
+    pub generic: bool,
 }
 
 impl Port {
@@ -147,6 +683,8 @@ impl Port {
             kind: PortKind::Int,
             slice: PortSlice::new_int(values),
             direction,
+            range: PortRange::Value,
+            generic: false,
         }
     }
 
@@ -156,18 +694,123 @@ impl Port {
             kind: PortKind::Float,
             slice: PortSlice::new_float(values),
             direction,
+            range: PortRange::Value,
+            generic: false,
         }
     }
 
     pub fn new_string_port(name: &str, values: Vec<&str>, direction: PortDirection) -> Port {
         Port {
             name: name.to_owned(),
-            kind: PortKind::Float,
+            kind: PortKind::String,
             slice: PortSlice::new_string(values),
             direction,
+            range: PortRange::Value,
+            generic: false,
+        }
+    }
+
+    pub fn new_bool_port(name: &str, values: Vec<bool>, direction: PortDirection) -> Port {
+        Port {
+            name: name.to_owned(),
+            kind: PortKind::Bool,
+            slice: PortSlice::new_bool(values),
+            direction,
+            range: PortRange::Value,
+            generic: false,
+        }
+    }
+
+    pub fn new_point_port(name: &str, values: Vec<Point>, direction: PortDirection) -> Port {
+        Port {
+            name: name.to_owned(),
+            kind: PortKind::Point,
+            slice: PortSlice::new_point(values),
+            direction,
+            range: PortRange::Value,
+            generic: false,
+        }
+    }
+
+    pub fn new_color_port(name: &str, values: Vec<Color>, direction: PortDirection) -> Port {
+        Port {
+            name: name.to_owned(),
+            kind: PortKind::Color,
+            slice: PortSlice::new_color(values),
+            direction,
+            range: PortRange::Value,
+            generic: false,
+        }
+    }
+
+    pub fn new_geometry_port(name: &str, values: Vec<Geometry>, direction: PortDirection) -> Port {
+        Port {
+            name: name.to_owned(),
+            kind: PortKind::Geometry,
+            slice: PortSlice::new_geometry(values),
+            direction,
+            range: PortRange::Value,
+            generic: false,
+        }
+    }
+
+    /// An input port that receives the whole list. It starts out empty.
+    pub fn new_list_input(name: &str, kind: PortKind) -> Port {
+        Port {
+            name: name.to_owned(),
+            kind,
+            slice: PortSlice::new_empty(kind),
+            direction: PortDirection::In,
+            range: PortRange::List,
+            generic: false,
+        }
+    }
+
+    /// An input port that receives nested lists unchanged. It starts out empty.
+    pub fn new_tree_input(name: &str, kind: PortKind) -> Port {
+        Port {
+            range: PortRange::Tree,
+            ..Port::new_list_input(name, kind)
+        }
+    }
+
+    /// A value input that accepts values of any kind. Its default value is a single 0.
+    pub fn new_generic_input(name: &str) -> Port {
+        Port {
+            generic: true,
+            ..Port::new(name, PortKind::Float, PortDirection::In)
+        }
+    }
+
+    /// A list input that accepts values of any kind.
+    pub fn new_generic_list_input(name: &str) -> Port {
+        Port {
+            generic: true,
+            ..Port::new_list_input(name, PortKind::Float)
+        }
+    }
+
+    /// A tree input that accepts values of any kind.
+    pub fn new_generic_tree_input(name: &str) -> Port {
+        Port {
+            generic: true,
+            ..Port::new_tree_input(name, PortKind::Float)
         }
     }
 
+    /// An output whose kind depends on the values the node receives.
+    pub fn new_generic_output(name: &str) -> Port {
+        Port {
+            generic: true,
+            ..Port::new(name, PortKind::Float, PortDirection::Out)
+        }
+    }
+
+    /// Whether this output can be connected to the given input, see `PortKind::can_convert_to`.
+    pub fn can_connect_to(&self, input: &Port) -> bool {
+        self.generic || input.generic || self.kind.can_convert_to(input.kind)
+    }
+
     pub fn new_output(name: &str, kind: PortKind) -> Port {
         Port::new(name, kind, PortDirection::Out)
     }
@@ -178,6 +821,8 @@ impl Port {
             kind,
             slice: PortSlice::new_single(kind),
             direction,
+            range: PortRange::Value,
+            generic: false,
         }
     }
 
@@ -197,6 +842,22 @@ impl Port {
         self.slice.get_string(index)
     }
 
+    pub fn get_bool(&self, index: usize) -> bool {
+        self.slice.get_bool(index)
+    }
+
+    pub fn get_point(&self, index: usize) -> Point {
+        self.slice.get_point(index)
+    }
+
+    pub fn get_color(&self, index: usize) -> Color {
+        self.slice.get_color(index)
+    }
+
+    pub fn get_geometry(&self, index: usize) -> Geometry {
+        self.slice.get_geometry(index)
+    }
+
     pub fn ensure_size(&mut self, new_size: usize) {
         self.slice.ensure_size(new_size)
     }
@@ -212,4 +873,192 @@ impl Port {
     pub fn set_string(&mut self, index: usize, v: &str) {
         self.slice.set_string(index, v);
     }
+
+    pub fn set_bool(&mut self, index: usize, v: bool) {
+        self.slice.set_bool(index, v);
+    }
+
+    pub fn set_point(&mut self, index: usize, v: Point) {
+        self.slice.set_point(index, v);
+    }
+
+    pub fn set_color(&mut self, index: usize, v: Color) {
+        self.slice.set_color(index, v);
+    }
+
+    pub fn set_geometry(&mut self, index: usize, v: Geometry) {
+        self.slice.set_geometry(index, v);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_around() {
+        let slice = PortSlice::new_point(vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)]);
+        assert_eq!(slice.get_point(3), Point::new(3.0, 4.0));
+        let slice = PortSlice::new_bool(vec![true, false, true]);
+        assert!(!slice.get_bool(4));
+    }
+
+    #[test]
+    fn conversions() {
+        let floats = PortSlice::new_float(vec![0.5, 0.0]);
+        assert_eq!(floats.get_point(0), Point::new(0.5, 0.5));
+        assert_eq!(floats.get_color(0), Color::gray(0.5));
+        assert!(floats.get_bool(0));
+        assert!(!floats.get_bool(1));
+
+        let strings = PortSlice::new_string(vec!["#ff8000", "#00000080", "nope", "3,4"]);
+        assert_eq!(
+            strings.get_color(0),
+            Color::new(1.0, 128.0 / 255.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            strings.get_color(1),
+            Color::new(0.0, 0.0, 0.0, 128.0 / 255.0)
+        );
+        assert_eq!(strings.get_color(2), Color::default());
+        assert_eq!(strings.get_point(3), Point::new(3.0, 4.0));
+
+        let colors = PortSlice::new_color(vec![Color::new(1.0, 0.0, 0.0, 0.5)]);
+        assert_eq!(colors.get_string(0), "#ff000080");
+
+        let bools = PortSlice::new_bool(vec![true]);
+        assert_eq!(bools.get_float(0), 1.0);
+        assert_eq!(bools.get_string(0), "true");
+
+        let points = PortSlice::new_point(vec![Point::new(1.0, 2.0)]);
+        assert_eq!(points.get_geometry(0).points().count(), 1);
+        assert_eq!(points.get_string(0), "1,2");
+    }
+
+    #[test]
+    fn set_and_ensure_size() {
+        let mut slice = PortSlice::new_single(PortKind::Color);
+        assert_eq!(slice.size(), 1);
+        slice.set_string(2, "#ffffff");
+        assert_eq!(slice.size(), 3);
+        assert_eq!(slice.get_color(1), Color::default());
+        assert_eq!(slice.get_color(2), Color::gray(1.0));
+
+        let mut slice = PortSlice::new_empty(PortKind::Point);
+        slice.set_float(0, 2.0);
+        slice.set_string(1, "5,6");
+        assert_eq!(slice.get_point(0), Point::new(2.0, 2.0));
+        assert_eq!(slice.get_point(1), Point::new(5.0, 6.0));
+
+        let mut slice = PortSlice::new_single(PortKind::Bool);
+        slice.set_float(0, 3.0);
+        assert!(slice.get_bool(0));
+    }
+
+    #[test]
+    fn nested_lists() {
+        let flat = PortSlice::new_float(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let nested = flat.group(2);
+        assert!(nested.is_nested());
+        assert_eq!(nested.depth(), 2);
+        assert_eq!(nested.size(), 3);
+        assert_eq!(nested.flat_size(), 5);
+        assert_eq!(nested.sublist(1), PortSlice::new_float(vec![3.0, 4.0]));
+        assert_eq!(nested.sublist(2), PortSlice::new_float(vec![5.0]));
+        assert_eq!(nested.get_float(2), 3.0);
+        assert_eq!(nested.get_float(6), 2.0);
+        assert_eq!(nested.flatten(), flat);
+        assert_eq!(nested.group(2).depth(), 3);
+        assert_eq!(nested.group(2).flatten(), flat);
+
+        let mut nested = nested;
+        nested.set_float(4, 50.0);
+        nested.set_float(5, 60.0);
+        assert_eq!(nested.sublist(2), PortSlice::new_float(vec![50.0, 60.0]));
+
+        let mut empty = PortSlice::new_list(PortKind::Int, vec![]);
+        empty.set_int(1, 7);
+        assert_eq!(empty.flatten(), PortSlice::new_int(vec![0, 7]));
+
+        // Lists without values return defaults.
+        let empty = PortSlice::new_empty(PortKind::Float);
+        let empty = PortSlice::new_list(PortKind::Float, vec![empty.clone(), empty]);
+        assert_eq!(empty.get_float(3), 0.0);
+        assert_eq!(empty.get_string(0), "");
+        assert_eq!(
+            PortSlice::new_empty(PortKind::Point).get_point(1),
+            Point::default()
+        );
+        assert_eq!(
+            PortSlice::new_list(PortKind::Int, vec![]).sublist(3),
+            PortSlice::new_empty(PortKind::Int)
+        );
+        assert_eq!(
+            PortSlice::new_empty(PortKind::String).select(&[0, 4]),
+            PortSlice::new_empty(PortKind::String)
+        );
+    }
+
+    #[test]
+    fn convert() {
+        let strings = PortSlice::new_string(vec!["1.5", " 2 ", "x"]);
+        assert_eq!(
+            strings.convert(PortKind::Float),
+            PortSlice::new_float(vec![1.5, 2.0, 0.0])
+        );
+        assert_eq!(
+            strings.convert(PortKind::Int),
+            PortSlice::new_int(vec![1, 2, 0])
+        );
+        assert_eq!(strings.try_convert(PortKind::Float), Err("x".to_owned()));
+        assert_eq!(
+            strings.group(2).try_convert(PortKind::Bool),
+            Err("1.5".to_owned())
+        );
+        assert_eq!(
+            strings.sub_slice(0, 2).try_convert(PortKind::Int),
+            Ok(PortSlice::new_int(vec![1, 2]))
+        );
+        let ints = PortSlice::new_int(vec![1, 2, 3]).group(2);
+        assert_eq!(
+            ints.convert(PortKind::String),
+            PortSlice::new_string(vec!["1", "2", "3"]).group(2)
+        );
+
+        let mut floats = PortSlice::new_single(PortKind::Float);
+        floats.set_string(0, "4.5");
+        assert_eq!(floats.get_float(0), 4.5);
+
+        assert!(PortKind::String.can_convert_to(PortKind::Float));
+        assert!(PortKind::Geometry.can_convert_to(PortKind::String));
+        assert!(PortKind::Point.can_convert_to(PortKind::Geometry));
+        assert!(!PortKind::Geometry.can_convert_to(PortKind::Float));
+        assert!(!PortKind::String.can_convert_to(PortKind::Geometry));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            PortSlice::parse(PortKind::Float, "1; 2.5;3"),
+            Some(PortSlice::new_float(vec![1.0, 2.5, 3.0]))
+        );
+        assert_eq!(PortSlice::parse(PortKind::Int, "1;x"), None);
+        assert_eq!(
+            PortSlice::parse(PortKind::String, "a;b "),
+            Some(PortSlice::new_string(vec!["a", "b "]))
+        );
+        assert_eq!(
+            PortSlice::parse(PortKind::Point, "1,2;3,4"),
+            Some(PortSlice::new_point(vec![
+                Point::new(1.0, 2.0),
+                Point::new(3.0, 4.0)
+            ]))
+        );
+        assert_eq!(
+            PortSlice::parse(PortKind::Bool, "true;false"),
+            Some(PortSlice::new_bool(vec![true, false]))
+        );
+        assert_eq!(PortSlice::parse(PortKind::Color, "red"), None);
+        assert_eq!(PortSlice::parse(PortKind::Geometry, ""), None);
+    }
 }
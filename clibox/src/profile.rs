@@ -0,0 +1,111 @@
+use crate::{Node, NodeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// What it cost to render a single node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStats {
+    pub id: NodeId,
+    pub name: String,
+    pub type_name: String,
+    /// How often the node was rendered. Renders that reused cached outputs are not counted.
+    pub invocations: usize,
+    /// The total time spent in the node's function.
+    pub time: Duration,
+    /// The number of values on each input and output port during the last invocation.
+    pub input_sizes: Vec<usize>,
+    pub output_sizes: Vec<usize>,
+}
+
+/// Per-node statistics, collected while rendering if profiling is turned on with
+/// `RenderContext::enable_profiling`. Statistics add up over all renders done with the context.
+#[derive(Debug, Default, Clone)]
+pub struct RenderStats {
+    nodes: HashMap<NodeId, NodeStats>,
+}
+
+impl RenderStats {
+    pub fn new() -> RenderStats {
+        RenderStats::default()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&NodeStats> {
+        self.nodes.get(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// The time spent in all nodes.
+    pub fn total_time(&self) -> Duration {
+        self.nodes.values().map(|stats| stats.time).sum()
+    }
+
+    /// The statistics of all rendered nodes, the most expensive first.
+    pub fn report(&self) -> Vec<&NodeStats> {
+        let mut report: Vec<&NodeStats> = self.nodes.values().collect();
+        report.sort_by(|a, b| b.time.cmp(&a.time).then(a.id.cmp(&b.id)));
+        report
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        node: &Node,
+        time: Duration,
+        input_sizes: Vec<usize>,
+        output_sizes: Vec<usize>,
+    ) {
+        let stats = self.nodes.entry(node.id).or_insert_with(|| NodeStats {
+            id: node.id,
+            name: node.name.clone(),
+            type_name: node.type_name.clone(),
+            invocations: 0,
+            time: Duration::default(),
+            input_sizes: Vec::new(),
+            output_sizes: Vec::new(),
+        });
+        stats.invocations += 1;
+        stats.time += time;
+        stats.input_sizes = input_sizes;
+        stats.output_sizes = output_sizes;
+    }
+}
+
+fn format_sizes(sizes: &[usize]) -> String {
+    let sizes: Vec<String> = sizes.iter().map(|size| size.to_string()).collect();
+    sizes.join(",")
+}
+
+/// A table with one row per node, the most expensive first.
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:<16} {:>6} {:>10} {:>6}  {:<16} outputs",
+            "node", "type", "calls", "time (ms)", "%", "inputs"
+        )?;
+        let total = self.total_time().as_secs_f64();
+        for stats in self.report() {
+            let time = stats.time.as_secs_f64();
+            let percentage = if total > 0.0 {
+                time / total * 100.0
+            } else {
+                0.0
+            };
+            writeln!(
+                f,
+                "{:<20} {:<16} {:>6} {:>10.3} {:>6.1}  {:<16} {}",
+                stats.name,
+                stats.type_name,
+                stats.invocations,
+                time * 1000.0,
+                percentage,
+                format_sizes(&stats.input_sizes),
+                format_sizes(&stats.output_sizes)
+            )?;
+        }
+        writeln!(f, "total: {:.3} ms", total * 1000.0)
+    }
+}
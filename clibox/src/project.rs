@@ -0,0 +1,416 @@
+//! Reading and writing networks as JSON project files.
+//!
+//! The format is described in the clibox README. Nodes are stored by type name and rebuilt
+//! through a `FunctionRepository`, so only port values that differ from the defaults are saved.
+
+use crate::{
+    new_node, new_subnetwork_node, Connection, FunctionRepository, ListMatching, Network,
+    NetworkError, Node, NodeId, PortSlice, PublishedPort,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+/// The version written to new project files. Files with a newer version are rejected.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    /// The node type is not registered in the function repository.
+    UnknownNodeType(NodeId, String),
+    /// The node has no input port with the given name.
+    UnknownPort(NodeId, String),
+    /// The values of the named input port can't be converted to the kind of the port.
+    InvalidValue(NodeId, String),
+    /// A node id, connection or published port in the file is invalid.
+    Network(NetworkError),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProjectError::Io(err) => write!(f, "Could not access project file: {}", err),
+            ProjectError::Json(err) => write!(f, "Invalid project file: {}", err),
+            ProjectError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported project file version {}.", version)
+            }
+            ProjectError::UnknownNodeType(id, type_name) => {
+                write!(f, "Node {} has unknown type \"{}\".", id, type_name)
+            }
+            ProjectError::UnknownPort(id, name) => {
+                write!(f, "Node {} has no input port \"{}\".", id, name)
+            }
+            ProjectError::InvalidValue(id, name) => {
+                write!(f, "Node {} has invalid values for port \"{}\".", id, name)
+            }
+            ProjectError::Network(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ProjectError {}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(err: std::io::Error) -> ProjectError {
+        ProjectError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(err: serde_json::Error) -> ProjectError {
+        ProjectError::Json(err)
+    }
+}
+
+impl From<NetworkError> for ProjectError {
+    fn from(err: NetworkError) -> ProjectError {
+        ProjectError::Network(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct FileVersion {
+    format_version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectFile {
+    format_version: u32,
+    network: NetworkData,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NetworkData {
+    rendered_id: NodeId,
+    nodes: Vec<NodeData>,
+    #[serde(default)]
+    connections: Vec<Connection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    published_inputs: Vec<PublishedPort>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeData {
+    #[serde(rename = "type")]
+    type_name: String,
+    id: NodeId,
+    name: String,
+    x: i32,
+    y: i32,
+    #[serde(default, skip_serializing_if = "is_default_list_matching")]
+    list_matching: ListMatching,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, PortSlice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    network: Option<NetworkData>,
+}
+
+fn is_default_list_matching(list_matching: &ListMatching) -> bool {
+    *list_matching == ListMatching::default()
+}
+
+pub fn network_to_json(
+    network: &Network,
+    repository: &FunctionRepository,
+) -> Result<String, ProjectError> {
+    let file = ProjectFile {
+        format_version: FORMAT_VERSION,
+        network: network_to_data(network, repository),
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+pub fn network_from_json(
+    json: &str,
+    repository: &FunctionRepository,
+) -> Result<Network, ProjectError> {
+    let version: FileVersion = serde_json::from_str(json)?;
+    if version.format_version > FORMAT_VERSION {
+        return Err(ProjectError::UnsupportedVersion(version.format_version));
+    }
+    let file: ProjectFile = serde_json::from_str(json)?;
+    network_from_data(file.network, repository)
+}
+
+pub fn save_network<P: AsRef<std::path::Path>>(
+    path: P,
+    network: &Network,
+    repository: &FunctionRepository,
+) -> Result<(), ProjectError> {
+    let json = network_to_json(network, repository)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+pub fn load_network<P: AsRef<std::path::Path>>(
+    path: P,
+    repository: &FunctionRepository,
+) -> Result<Network, ProjectError> {
+    let json = fs::read_to_string(path)?;
+    network_from_json(&json, repository)
+}
+
+fn network_to_data(network: &Network, repository: &FunctionRepository) -> NetworkData {
+    NetworkData {
+        rendered_id: network.rendered_id,
+        nodes: network
+            .nodes
+            .iter()
+            .map(|node| node_to_data(node, repository))
+            .collect(),
+        connections: network.connections.clone(),
+        published_inputs: network.published_inputs.clone(),
+    }
+}
+
+fn node_to_data(node: &Node, repository: &FunctionRepository) -> NodeData {
+    // Compare against a fresh node to find out which values were changed.
+    // Nodes that can't be recreated from the repository have all their values saved.
+    let defaults = new_node(repository, node.id, &node.type_name, 0, 0);
+    let mut values = BTreeMap::new();
+    for port in &node.inputs {
+        let default = defaults
+            .as_ref()
+            .and_then(|d| d.get_input_by_name(&port.name));
+        if default.map(|d| &d.slice) != Some(&port.slice) {
+            values.insert(port.name.clone(), port.slice.clone());
+        }
+    }
+    NodeData {
+        type_name: node.type_name.clone(),
+        id: node.id,
+        name: node.name.clone(),
+        x: node.x,
+        y: node.y,
+        list_matching: node.list_matching,
+        values,
+        network: node
+            .function
+            .subnetwork()
+            .map(|network| network_to_data(network, repository)),
+    }
+}
+
+/// A copy of the node with the given id, made the same way as saving and loading it.
+/// Returns None if the node type is not in the repository.
+pub(crate) fn copy_node(node: &Node, id: NodeId, repository: &FunctionRepository) -> Option<Node> {
+    let mut data = node_to_data(node, repository);
+    data.id = id;
+    node_from_data(data, repository).ok()
+}
+
+fn network_from_data(
+    data: NetworkData,
+    repository: &FunctionRepository,
+) -> Result<Network, ProjectError> {
+    let mut network = Network::new();
+    for node_data in data.nodes {
+        if network.get_node(node_data.id).is_some() {
+            return Err(NetworkError::DuplicateId(node_data.id).into());
+        }
+        let node = node_from_data(node_data, repository)?;
+        network.nodes.push(node);
+    }
+    for conn in data.connections {
+        network.connect(
+            conn.output_id,
+            conn.output_port,
+            conn.input_id,
+            conn.input_port,
+        )?;
+    }
+    for published in data.published_inputs {
+        network.publish_input(&published.name, published.node_id, published.port)?;
+    }
+    network.rendered_id = data.rendered_id;
+    Ok(network)
+}
+
+fn node_from_data(data: NodeData, repository: &FunctionRepository) -> Result<Node, ProjectError> {
+    let mut node = match data.network {
+        Some(network_data) => {
+            let inner = network_from_data(network_data, repository)?;
+            new_subnetwork_node(data.id, &data.name, inner, data.x, data.y)
+        }
+        None => match new_node(repository, data.id, &data.type_name, data.x, data.y) {
+            Some(node) => node,
+            None => return Err(ProjectError::UnknownNodeType(data.id, data.type_name)),
+        },
+    };
+    node.name = data.name;
+    node.list_matching = data.list_matching;
+    // Values of another kind are converted, as when they are set by an edit.
+    for (name, slice) in data.values {
+        let port = match node.get_input_by_name_mut(&name) {
+            Some(port) => port,
+            None => return Err(ProjectError::UnknownPort(data.id, name)),
+        };
+        if !slice.has_consistent_kind() {
+            return Err(ProjectError::InvalidValue(data.id, name));
+        }
+        port.slice = if port.generic || slice.kind() == port.kind {
+            slice
+        } else {
+            match slice.try_convert(port.kind) {
+                Ok(converted) if slice.kind().can_convert_to(port.kind) => converted,
+                _ => return Err(ProjectError::InvalidValue(data.id, name)),
+            }
+        };
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RenderContext;
+
+    fn render(network: &Network) -> PortSlice {
+        let mut ctx = RenderContext::new(network);
+        network.render(&mut ctx).unwrap();
+        ctx.get_output_slice(network.rendered_id, 0)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn round_trip() {
+        let repo = FunctionRepository::with_builtins();
+        let mut inner = Network::new();
+        inner.nodes.push(new_node(&repo, 1, "Add", 0, 0).unwrap());
+        inner.rendered_id = 1;
+        inner.publish_input("a", 1, 0).unwrap();
+
+        let mut network = Network::new();
+        let mut floats = new_node(&repo, 1, "Parse Floats", 10, 20).unwrap();
+        floats.set_string("s", 0, "1;2;3");
+        floats.name = "floats1".to_owned();
+        network.nodes.push(floats);
+        let mut add = new_node(&repo, 2, "Add", 10, 40).unwrap();
+        add.set_float("b", 0, 100.0);
+        add.set_float("b", 1, 200.0);
+        add.set_list_matching(ListMatching::CrossProduct);
+        network.nodes.push(add);
+        network
+            .nodes
+            .push(new_subnetwork_node(3, "wrapped", inner, 10, 60));
+        network.connect(1, 0, 2, 0).unwrap();
+        network.connect(2, 0, 3, 0).unwrap();
+        network.rendered_id = 3;
+
+        let json = network_to_json(&network, &repo).unwrap();
+        // Default values are not saved.
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let add_values = &value["network"]["nodes"][1]["values"];
+        assert!(add_values.get("a").is_none());
+        assert!(add_values.get("b").is_some());
+        let loaded = network_from_json(&json, &repo).unwrap();
+        assert_eq!(loaded.nodes.len(), 3);
+        assert_eq!(loaded.connections, network.connections);
+        assert_eq!(loaded.rendered_id, 3);
+        let floats = loaded.get_node(1).unwrap();
+        assert_eq!(floats.name, "floats1");
+        assert_eq!(floats.type_name, "Parse Floats");
+        assert_eq!((floats.x, floats.y), (10, 20));
+        assert_eq!(
+            loaded.get_node(2).unwrap().list_matching,
+            ListMatching::CrossProduct
+        );
+        assert_eq!(loaded.get_node(3).unwrap().type_name, "Subnetwork");
+        assert_eq!(render(&loaded), render(&network));
+        assert_eq!(render(&loaded).size(), 6);
+    }
+
+    #[test]
+    fn unknown_node_type() {
+        let repo = FunctionRepository::with_builtins();
+        let json = r#"{
+            "format_version": 1,
+            "network": {
+                "rendered_id": 1,
+                "nodes": [{"type": "Teleport", "id": 1, "name": "teleport1", "x": 0, "y": 0}]
+            }
+        }"#;
+        match network_from_json(json, &repo) {
+            Err(ProjectError::UnknownNodeType(1, type_name)) => assert_eq!(type_name, "Teleport"),
+            _ => panic!("Expected an unknown node type error."),
+        }
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let repo = FunctionRepository::with_builtins();
+        let json = r#"{
+            "format_version": 1,
+            "network": {
+                "rendered_id": 1,
+                "nodes": [
+                    {"type": "Value", "id": 1, "name": "value1", "x": 0, "y": 0},
+                    {"type": "Add", "id": 1, "name": "add1", "x": 0, "y": 1}
+                ]
+            }
+        }"#;
+        assert!(matches!(
+            network_from_json(json, &repo),
+            Err(ProjectError::Network(NetworkError::DuplicateId(1)))
+        ));
+    }
+
+    #[test]
+    fn value_kinds() {
+        let repo = FunctionRepository::with_builtins();
+        let json = |values: &str| {
+            format!(
+                r#"{{
+                    "format_version": 1,
+                    "network": {{
+                        "rendered_id": 1,
+                        "nodes": [{{"type": "Add", "id": 1, "name": "add1", "x": 0, "y": 0,
+                                    "values": {}}}]
+                    }}
+                }}"#,
+                values
+            )
+        };
+        // Values of another kind are converted to the kind of the port.
+        let network = network_from_json(&json(r#"{"a": {"Int": [2, 3]}}"#), &repo).unwrap();
+        assert_eq!(
+            network.get_node(1).unwrap().inputs[0].slice,
+            PortSlice::new_float(vec![2.0, 3.0])
+        );
+        let nested = r#"{"a": {"List": ["Int", [{"Int": [1]}, {"Int": [2]}]]}}"#;
+        let network = network_from_json(&json(nested), &repo).unwrap();
+        assert_eq!(
+            network.get_node(1).unwrap().inputs[0].slice,
+            PortSlice::new_float(vec![1.0, 2.0]).group(1)
+        );
+        // Values that can't be converted and nested lists that hold another kind than they
+        // declare are rejected.
+        for values in &[
+            r#"{"a": {"Geometry": []}}"#,
+            r#"{"a": {"String": ["x"]}}"#,
+            r#"{"a": {"List": ["Float", [{"String": ["1"]}]]}}"#,
+            r#"{"a": {"List": ["Float", [{"List": ["Float", [{"Int": [1]}]]}]]}}"#,
+        ] {
+            match network_from_json(&json(values), &repo) {
+                Err(ProjectError::InvalidValue(1, name)) => assert_eq!(name, "a"),
+                _ => panic!("Expected an invalid value error."),
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let repo = FunctionRepository::with_builtins();
+        let json = r#"{"format_version": 99, "network": {}}"#;
+        assert!(matches!(
+            network_from_json(json, &repo),
+            Err(ProjectError::UnsupportedVersion(99))
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+//! A small seeded random number generator, so random nodes give the same results on every
+//! platform and every run.
+
+/// SplitMix64. Not suitable for cryptography.
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number between 0 (inclusive) and 1 (exclusive). Uses the top 24 bits, which an f32
+    /// represents exactly, so the result doesn't depend on float rounding.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// A number between min and max, both inclusive. The order of min and max doesn't matter.
+    pub fn next_int(&mut self, min: i32, max: i32) -> i32 {
+        let (min, max) = if min <= max { (min, max) } else { (max, min) };
+        let span = (i64::from(max) - i64::from(min) + 1) as u64;
+        (i64::from(min) + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// An index between 0 (inclusive) and n (exclusive). n must be larger than 0.
+    pub fn next_index(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Put the items in a random order (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.next_index(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        let mut c = Random::new(43);
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+        // The reference output of SplitMix64 for seed 0.
+        assert_eq!(Random::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut random = Random::new(1);
+        for _ in 0..1000 {
+            assert!(random.next_index(3) < 3);
+            let f = random.next_f32();
+            assert!((0.0..1.0).contains(&f));
+            let i = random.next_int(5, -5);
+            assert!((-5..=5).contains(&i));
+        }
+        let mut items: Vec<usize> = (0..10).collect();
+        random.shuffle(&mut items);
+        assert_ne!(items, (0..10).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
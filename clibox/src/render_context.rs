@@ -1,50 +1,321 @@
-use crate::{Network, NodeId, Port, PortIndex, PortSlice};
-use std::collections::HashMap;
+use crate::{
+    ListMatch, Network, NetworkError, Node, NodeId, Port, PortIndex, PortRange, PortSlice,
+    RenderStats,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 
+/// Outputs of a previous render, kept between renders so only dirty nodes are recomputed.
+///
+/// A `RenderContext` borrows the network, so the cache is moved in and out of it:
+/// create the context with `RenderContext::with_cache` and take the cache back with `into_cache`.
+#[derive(Default)]
+pub struct RenderCache {
+    outputs: HashMap<(NodeId, PortIndex), Arc<PortSlice>>,
+    revisions: HashMap<NodeId, u64>,
+    frames: HashMap<NodeId, (i32, f32)>,
+    generations: HashMap<NodeId, u64>,
+    next_generation: u64,
+}
+
+impl RenderCache {
+    pub fn new() -> RenderCache {
+        RenderCache::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.outputs.clear();
+        self.revisions.clear();
+        self.frames.clear();
+        self.generations.clear();
+    }
+}
+
+/// Port values are reference counted: passing an output to the inputs connected to it shares
+/// the values instead of copying them. Use `get_output_slice_mut` to change a value in place;
+/// it copies the values first if they are shared.
 pub struct RenderContext<'n> {
     pub network: &'n Network,
-    pub inputs: HashMap<(NodeId, PortIndex), PortSlice>,
-    pub outputs: HashMap<(NodeId, PortIndex), PortSlice>,
+    pub inputs: HashMap<(NodeId, PortIndex), Arc<PortSlice>>,
+    /// Values that replace the stored port values, see `set_input_value`.
+    pub input_values: HashMap<(NodeId, PortIndex), Arc<PortSlice>>,
+    pub outputs: HashMap<(NodeId, PortIndex), Arc<PortSlice>>,
+    /// The node revision each cached output was rendered with.
+    pub revisions: HashMap<NodeId, u64>,
+    /// The frame and frame rate each cached output of a time dependent node was rendered with.
+    pub frames: HashMap<NodeId, (i32, f32)>,
+    /// When each cached output was rendered, counting up. A node whose upstream node has a
+    /// higher generation was rendered with outputs that have since changed.
+    pub generations: HashMap<NodeId, u64>,
+    next_generation: u64,
+    /// The frame being rendered, starting at 0.
+    pub frame: i32,
+    /// Frames per second, used to convert the frame to a time.
+    pub fps: f32,
+    /// Nodes recomputed during the current render. Used for reporting only; whether a node is
+    /// dirty depends on `generations`.
+    pub rendered: HashSet<NodeId>,
+    /// Nodes that failed or were skipped during the current render.
+    pub errors: HashMap<NodeId, NetworkError>,
+    /// The number of threads to render with. Nodes that don't depend on each other are rendered
+    /// at the same time if this is more than 1. The outputs are the same as with a single thread.
+    pub threads: usize,
+    /// Per-node statistics, only collected after `enable_profiling`.
+    pub stats: Option<RenderStats>,
 }
 
-impl<'n, 'f> RenderContext<'n> {
+impl<'n> RenderContext<'n> {
     pub fn new(network: &'n Network) -> RenderContext<'n> {
+        RenderContext::with_cache(network, RenderCache::new())
+    }
+
+    pub fn with_cache(network: &'n Network, cache: RenderCache) -> RenderContext<'n> {
         RenderContext {
             network,
             inputs: HashMap::new(),
-            outputs: HashMap::new(),
+            input_values: HashMap::new(),
+            outputs: cache.outputs,
+            revisions: cache.revisions,
+            frames: cache.frames,
+            generations: cache.generations,
+            next_generation: cache.next_generation,
+            frame: 0,
+            fps: 30.0,
+            rendered: HashSet::new(),
+            errors: HashMap::new(),
+            threads: 1,
+            stats: None,
+        }
+    }
+
+    /// Give back the outputs so they can be reused by the next render.
+    /// Outputs of nodes that are no longer in the network are dropped.
+    pub fn into_cache(self) -> RenderCache {
+        let network = self.network;
+        let mut outputs = self.outputs;
+        let mut revisions = self.revisions;
+        let mut frames = self.frames;
+        let mut generations = self.generations;
+        outputs.retain(|(id, _), _| network.get_node(*id).is_some());
+        revisions.retain(|id, _| network.get_node(*id).is_some());
+        frames.retain(|id, _| network.get_node(*id).is_some());
+        generations.retain(|id, _| network.get_node(*id).is_some());
+        RenderCache {
+            outputs,
+            revisions,
+            frames,
+            generations,
+            next_generation: self.next_generation,
+        }
+    }
+
+    /// Collect statistics about every node that is rendered from now on, see `stats`.
+    pub fn enable_profiling(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(RenderStats::new());
+        }
+    }
+
+    /// Record how long it took to render the node, if profiling is enabled.
+    pub(crate) fn record_stats(&mut self, node: &Node, time: Duration) {
+        if self.stats.is_none() {
+            return;
+        }
+        let input_sizes = node
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, _)| self.get_input_slice(node.id, i).flat_size())
+            .collect();
+        let output_sizes = (0..node.outputs.len())
+            .map(|i| self.outputs.get(&(node.id, i)).map_or(0, |s| s.flat_size()))
+            .collect();
+        if let Some(stats) = self.stats.as_mut() {
+            stats.record(node, time, input_sizes, output_sizes);
+        }
+    }
+
+    /// The time of the current frame in seconds.
+    pub fn time(&self) -> f32 {
+        self.frame as f32 / self.fps
+    }
+
+    /// Prepare the context for a new render. Cached outputs are kept.
+    pub fn begin_render(&mut self) {
+        self.inputs.clear();
+        self.rendered.clear();
+        self.errors.clear();
+    }
+
+    pub fn get_error(&self, id: NodeId) -> Option<&NetworkError> {
+        self.errors.get(&id)
+    }
+
+    /// Record that the node failed to render. Its outputs are dropped from the cache so it is
+    /// rendered again next time.
+    pub fn set_error(&mut self, id: NodeId, error: NetworkError) {
+        self.outputs.retain(|(output_id, _), _| *output_id != id);
+        self.revisions.remove(&id);
+        self.frames.remove(&id);
+        self.generations.remove(&id);
+        self.errors.insert(id, error);
+    }
+
+    /// Returns the node that caused one of the upstream nodes of this node to fail.
+    pub fn failed_upstream(&self, node: &Node) -> Option<NodeId> {
+        self.network
+            .connections
+            .iter()
+            .filter(|c| c.input_id == node.id)
+            .find_map(|c| match self.errors.get(&c.output_id) {
+                Some(NetworkError::UpstreamFailed(_, failed_id)) => Some(*failed_id),
+                Some(_) => Some(c.output_id),
+                None => None,
+            })
+    }
+
+    /// A node is dirty if it changed since its outputs were cached, if it depends on time and
+    /// was cached for another frame, or if one of its upstream nodes was recomputed after it,
+    /// in this render or in an earlier one.
+    pub fn is_dirty(&self, node: &Node) -> bool {
+        if self.revisions.get(&node.id) != Some(&node.revision()) {
+            return true;
         }
+        if node.is_time_dependent() && self.frames.get(&node.id) != Some(&(self.frame, self.fps)) {
+            return true;
+        }
+        let generation = match self.generations.get(&node.id) {
+            Some(generation) => *generation,
+            None => return true,
+        };
+        self.network
+            .connections
+            .iter()
+            .filter(|c| c.input_id == node.id)
+            .any(|c| {
+                self.generations
+                    .get(&c.output_id)
+                    .map_or(true, |upstream| *upstream > generation)
+            })
+    }
+
+    /// Record that the node was recomputed with its current revision.
+    pub fn mark_rendered(&mut self, node: &Node) {
+        self.revisions.insert(node.id, node.revision());
+        if node.is_time_dependent() {
+            self.frames.insert(node.id, (self.frame, self.fps));
+        }
+        self.next_generation += 1;
+        self.generations.insert(node.id, self.next_generation);
+        self.rendered.insert(node.id);
     }
 
     pub fn set_output_floats(&mut self, id: NodeId, output_port: PortIndex, values: Vec<f32>) {
-        self.outputs
-            .insert((id, output_port), PortSlice::new_float(values));
+        self.set_output_slice(id, output_port, PortSlice::new_float(values));
     }
 
     pub fn get_output_slice(&mut self, id: NodeId, output_port: PortIndex) -> Option<&PortSlice> {
-        self.outputs.get(&(id, output_port))
+        self.outputs.get(&(id, output_port)).map(|slice| &**slice)
+    }
+
+    /// The values of an output, to be changed in place. The values are copied first if they
+    /// are shared with other ports.
+    pub fn get_output_slice_mut(
+        &mut self,
+        id: NodeId,
+        output_port: PortIndex,
+    ) -> Option<&mut PortSlice> {
+        self.outputs.get_mut(&(id, output_port)).map(Arc::make_mut)
     }
 
     pub fn set_output_slice(&mut self, id: NodeId, output_port: PortIndex, slice: PortSlice) {
+        self.outputs.insert((id, output_port), Arc::new(slice));
+    }
+
+    /// Set an output to values that are shared with other ports, e.g. an input that is passed
+    /// through unchanged.
+    pub fn set_output_shared(&mut self, id: NodeId, output_port: PortIndex, slice: Arc<PortSlice>) {
         self.outputs.insert((id, output_port), slice);
     }
 
+    /// The values of an input port: the output of the connected node, or the value set with
+    /// `set_input_value`, or the value stored in the port.
     pub fn get_input_slice(&self, id: NodeId, input_port: PortIndex) -> &PortSlice {
         self.inputs
             .get(&(id, input_port))
+            .or_else(|| self.input_values.get(&(id, input_port)))
+            .map(|slice| &**slice)
             .unwrap_or_else(|| &self.network.get_input_port(id, input_port).unwrap().slice)
     }
 
+    /// Like `get_input_slice`, but returns a shared reference to the values. Values stored in
+    /// the port are copied.
+    pub fn get_input_shared(&self, id: NodeId, input_port: PortIndex) -> Arc<PortSlice> {
+        self.inputs
+            .get(&(id, input_port))
+            .or_else(|| self.input_values.get(&(id, input_port)))
+            .cloned()
+            .unwrap_or_else(|| {
+                let port = self.network.get_input_port(id, input_port).unwrap();
+                Arc::new(port.slice.clone())
+            })
+    }
+
+    /// Replace the stored value of an input port for the renders done with this context.
+    /// This is how a subnetwork passes its published inputs to the inner network.
+    /// The node is rendered again the next time it is needed.
+    pub fn set_input_value(&mut self, id: NodeId, input_port: PortIndex, slice: Arc<PortSlice>) {
+        self.input_values.insert((id, input_port), slice);
+        self.revisions.remove(&id);
+    }
+
+    /// Pass the output of a node to the connected input, converted to the kind of the input.
+    /// If no conversion is needed, the input shares the values of the output.
+    /// Fails if a string can't be parsed as the kind of the input.
     pub fn clone_output_to_input(
         &mut self,
         output_id: NodeId,
         output_port: PortIndex,
         input_id: NodeId,
         input_port: PortIndex,
-    ) {
+    ) -> Result<(), NetworkError> {
         if let Some(values) = self.outputs.get(&(output_id, output_port)) {
-            self.inputs.insert((input_id, input_port), values.clone());
+            let values = match self.network.get_input_port(input_id, input_port) {
+                Some(port) if !port.generic && port.kind != values.kind() => {
+                    match values.try_convert(port.kind) {
+                        Ok(converted) => Arc::new(converted),
+                        Err(value) => {
+                            return Err(NetworkError::RenderFailed(
+                                input_id,
+                                format!(
+                                    "Could not convert \"{}\" on input \"{}\" to {:?}.",
+                                    value, port.name, port.kind
+                                ),
+                            ));
+                        }
+                    }
+                }
+                _ => Arc::clone(values),
+            };
+            self.inputs.insert((input_id, input_port), values);
         }
+        Ok(())
+    }
+
+    /// The iterations of an element-wise function, based on the node's list matching and the
+    /// sizes of its value inputs. List inputs are read whole with `get_input_slice`.
+    pub fn list_match(&self, node: &Node) -> ListMatch {
+        let sizes = node
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| match p.range {
+                PortRange::Value => Some(self.get_input_size(node.id, p, i)),
+                PortRange::List | PortRange::Tree => None,
+            })
+            .collect();
+        ListMatch::with_list_ports(node.list_matching, sizes)
     }
 
     pub fn get_max_input_size(&self, id: NodeId) -> usize {
@@ -67,7 +338,7 @@ impl<'n, 'f> RenderContext<'n> {
     pub fn get_input_size(&self, id: NodeId, port: &Port, port_index: PortIndex) -> usize {
         self.inputs
             .get(&(id, port_index))
-            .unwrap_or_else(|| &port.slice)
-            .size()
+            .or_else(|| self.input_values.get(&(id, port_index)))
+            .map_or_else(|| port.slice.size(), |slice| slice.size())
     }
 }